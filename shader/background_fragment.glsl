@@ -0,0 +1,25 @@
+#version 330 core
+in vec2 TexCoords;
+out vec4 FragColor;
+
+uniform vec2 resolution;
+
+float hash(vec2 p) {
+  return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+void main() {
+  vec2 pixel = TexCoords * resolution;
+  vec3 color = vec3(0.2, 0.3, 0.3);
+
+  // Grid lines make the distortion easy to read
+  vec2 grid = abs(fract(pixel / 50.0 + 0.5) - 0.5) * 50.0;
+  float line = 1.0 - smoothstep(0.0, 1.0, min(grid.x, grid.y));
+  color = mix(color, vec3(0.3, 0.42, 0.42), line);
+
+  vec2 cell = floor(pixel / 4.0);
+  float star = step(0.995, hash(cell));
+  color = mix(color, vec3(1.0), star * hash(cell + 1.0));
+
+  FragColor = vec4(color, 1.0);
+}
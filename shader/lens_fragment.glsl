@@ -0,0 +1,29 @@
+#version 330 core
+in vec2 TexCoords;
+out vec4 FragColor;
+
+const int MAX_LENSES = 8;
+
+uniform sampler2D scene;
+uniform vec2 resolution;
+uniform int lensCount;
+// xy = screen position in pixels, z = Einstein radius, w = horizon radius
+uniform vec4 lenses[MAX_LENSES];
+
+void main() {
+  vec2 pixel = TexCoords * resolution;
+  vec2 source = pixel;
+
+  for (int i = 0; i < lensCount; i++) {
+    vec2 offset = pixel - lenses[i].xy;
+    float distSq = max(dot(offset, offset), 1.0);
+    if (distSq < lenses[i].w * lenses[i].w) {
+      FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+      return;
+    }
+    // Point lens: beta = theta - theta_E^2 / theta
+    source -= offset * (lenses[i].z * lenses[i].z) / distSq;
+  }
+
+  FragColor = texture(scene, source / resolution);
+}
@@ -0,0 +1,11 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec4 aColor;
+
+out vec4 vertexColor;
+uniform mat4 transform;
+
+void main() {
+  vertexColor = aColor;
+  gl_Position = transform * vec4(aPos, 1.0);
+}
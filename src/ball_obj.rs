@@ -1,9 +1,17 @@
-extern crate glfw;
-use glam::{Mat4, Vec3, Vec4};
+use std::collections::VecDeque;
 
-use crate::{SRC_HEIGHT, SRC_WIDTH, collision::*, line_renderer::LineRenderer};
+use glam::{Vec3, Vec4};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy)]
+use crate::{
+    collision::*,
+    gravity::{Gravity, Potential},
+    segment_obj::SegmentObject,
+    square_obj::SquareObject,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -16,6 +24,17 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    /// Mixes towards `other` by `t`, where 0 keeps `self` and 1 gives `other`.
+    pub fn blend(self, other: Color, t: f32) -> Self {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+
     pub fn to_vec(self) -> Vec4 {
         Vec4::new(
             self.r as f32 / 255.0,
@@ -28,17 +47,21 @@ impl Color {
 
 #[derive(Clone)]
 pub struct BallObject {
+    /// Assigned by `World::add_ball`. Only the survivor of a merge keeps its id, see
+    /// `World::absorbed`.
+    pub id: u64,
     pub position: Vec3,
+    pub previous_position: Vec3,
     pub velocity: Vec3,
     pub radius: f32,
     pub color: Color,
     pub mass: f32,
     pub has_collision: bool,
     pub has_gravity: bool,
-    vao: u32,
-    vbo: u32,
-    ebo: u32,
-    vertex_count: i32,
+    pub is_black_hole: bool,
+    pub potential: Potential,
+    /// Recent positions, oldest first
+    pub trail: VecDeque<Vec3>,
 }
 
 impl BallObject {
@@ -52,208 +75,147 @@ impl BallObject {
         has_gravity: bool,
     ) -> Self {
         BallObject {
+            id: 0,
             position,
+            previous_position: position,
             velocity,
             radius,
             color,
             mass,
             has_collision,
             has_gravity,
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
-            vertex_count: 0,
+            is_black_hole: false,
+            potential: Potential::Newtonian,
+            trail: VecDeque::new(),
         }
     }
 
-    pub fn update(&mut self, delta_time: f32) {
-        self.position.x += self.velocity.x * delta_time;
-        self.position.y += self.velocity.y * delta_time;
-    }
+    pub fn wall_collision(&mut self, width: f32, height: f32, restitution: f32) {
+        let wall = check_wall_collision(self.position, self.radius, width, height);
 
-    pub fn gravity_update(&mut self, another_ball: &BallObject, delta_time: f32) {
-        if !self.has_gravity {
-            return;
-        }
-        //F = G * (m1 * m2) / r^2
-        let g = 100.0;
-        let direction = another_ball.position - self.position;
-        let r = direction.length();
-        if r < 1.0 {
-            return;
+        if wall.left || wall.right {
+            self.velocity.x *= -restitution;
+            if wall.left {
+                self.position.x = self.radius;
+            } else if wall.right {
+                self.position.x = width - self.radius;
+            }
         }
 
-        let f = g * (self.mass * another_ball.mass) / r.powi(2);
-
-        let acceleration = (direction.normalize() * f) / self.mass;
-        self.velocity += acceleration * delta_time;
+        if wall.top || wall.bottom {
+            self.velocity.y *= -restitution;
+            if wall.bottom {
+                self.position.y = self.radius;
+            } else if wall.top {
+                self.position.y = height - self.radius;
+            }
+        }
     }
 
-    pub fn render(&mut self, shader_program: u32, projection: &Mat4) {
-        self.mesh();
-        let line_renderer = LineRenderer::new();
-        unsafe {
-            gl::UseProgram(shader_program);
-
-            let mut model = glam::Mat4::IDENTITY;
-
-            model *= Mat4::from_translation(self.position);
-
-            let transform = *projection * model;
-
-            let transform_name = std::ffi::CString::new("transform").unwrap();
-            let transformloc = gl::GetUniformLocation(shader_program, transform_name.as_ptr());
-            gl::UniformMatrix4fv(
-                transformloc,
-                1,
-                gl::FALSE,
-                &transform as *const Mat4 as *const f32,
-            );
-
-            let color_name = std::ffi::CString::new("objectColor").unwrap();
-            let colorloc = gl::GetUniformLocation(shader_program, color_name.as_ptr());
-            gl::Uniform4f(
-                colorloc,
-                self.color.to_vec().x,
-                self.color.to_vec().y,
-                self.color.to_vec().z,
-                self.color.to_vec().w,
-            );
-
-            gl::BindVertexArray(self.vao);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.vertex_count,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
-            line_renderer.draw_vector(
-                self.position,
-                Vec3::new(self.velocity.x, self.velocity.y, 0.0),
-                50.0 + self.radius,
-                Vec3::new(1.0, 0.0, 0.0),
-                shader_program,
-                projection,
-            );
+    /// Moves the ball to the opposite edge once its center leaves the screen.
+    pub fn wrap(&mut self, width: f32, height: f32) {
+        let wrapped = wrap_position(self.position, width, height);
+        if wrapped != self.position {
+            // Shift the previous position along so interpolation doesn't sweep across the screen
+            self.previous_position += wrapped - self.position;
+            self.position = wrapped;
+            self.trail.clear();
         }
     }
 
-    fn mesh(&mut self) {
-        let segments = 32;
-        let mut vertices: Vec<f32> = Vec::new();
-        let mut indices: Vec<u32> = Vec::new();
+    pub fn check_ball_square_collision(&mut self, square: &SquareObject) {
+        if !self.has_collision {
+            return;
+        }
+        let (collided, side_index, ball_pos) = check_ball_square_collision(
+            self.position,
+            self.radius,
+            square.position,
+            square.size,
+            square.rotation,
+        );
+        if collided {
+            let normal = square.get_normal_relative_to(side_index);
 
-        vertices.extend_from_slice(&[0.0, 0.0, 0.0]);
+            self.position = ball_pos;
 
-        for i in 0..=segments {
-            let angle = (i as f32 / segments as f32) * 2.0 * std::f32::consts::PI;
-            let x = angle.cos() * self.radius;
-            let y = angle.sin() * self.radius;
-            vertices.extend_from_slice(&[x, y, 0.0]);
+            let vel_along_normal = self.velocity.dot(normal);
+            if vel_along_normal < 0.0 {
+                self.velocity -= 2.0 * vel_along_normal * normal;
+            }
         }
+    }
 
-        for i in 1..=segments {
-            indices.push(0);
-            indices.push(i);
-            indices.push(i + 1);
+    pub fn check_ball_segment_collision(&mut self, segment: &SegmentObject) {
+        if !self.has_collision {
+            return;
         }
-        self.vertex_count = indices.len() as i32;
-        unsafe {
-            gl::GenVertexArrays(1, &mut self.vao);
-            gl::GenBuffers(1, &mut self.vbo);
-            gl::GenBuffers(1, &mut self.ebo);
-
-            gl::BindVertexArray(self.vao);
-
-            //VBO
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (vertices.len() * std::mem::size_of::<f32>()) as isize,
-                vertices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            //EBO
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (indices.len() * std::mem::size_of::<u32>()) as isize,
-                indices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            //position
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                (3 * std::mem::size_of::<f32>()) as i32,
-                std::ptr::null(),
-            );
-            gl::EnableVertexAttribArray(0);
+        let (collided, normal, ball_pos) =
+            check_ball_segment_collision(self.position, self.radius, segment.start, segment.end);
+        if collided {
+            self.position = ball_pos;
+
+            let vel_along_normal = self.velocity.dot(normal);
+            if vel_along_normal < 0.0 {
+                self.velocity -= 2.0 * vel_along_normal * normal;
+            }
+        }
+    }
 
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            gl::BindVertexArray(0);
+    /// Balls take part in rigid contacts without spinning.
+    pub fn contact_body(&self) -> ContactBody {
+        ContactBody {
+            center: self.position.truncate(),
+            velocity: self.velocity.truncate(),
+            angular_velocity: 0.0,
+            inverse_mass: if self.mass > 0.0 {
+                1.0 / self.mass
+            } else {
+                0.0
+            },
+            inverse_inertia: 0.0,
         }
     }
 
-    pub fn wall_collision(&mut self) {
-        let damping = 0.85;
-        let wall = check_wall_collision(
-            self.position,
-            self.radius,
-            SRC_WIDTH as f32,
-            SRC_HEIGHT as f32,
-        );
+    pub fn apply_contact_body(&mut self, body: &ContactBody) {
+        self.position = body.center.extend(self.position.z);
+        self.velocity = body.velocity.extend(self.velocity.z);
+    }
 
-        if wall.left || wall.right {
-            self.velocity.x *= -damping * 1.0;
-            if wall.left {
-                self.position.x = self.radius;
-            } else if wall.right {
-                self.position.x = SRC_WIDTH as f32 - self.radius;
-            }
+    /// Schwarzschild radius the potential of this ball uses, 0 for plain Newtonian gravity.
+    pub fn horizon(&self, gravity: &Gravity) -> f32 {
+        match self.potential {
+            Potential::Newtonian => 0.0,
+            Potential::PaczynskiWiita => gravity.schwarzschild_radius(self.mass),
         }
+    }
 
-        if wall.top || wall.bottom {
-            self.velocity.y *= -damping * 1.0;
-            if wall.bottom {
-                self.position.y = self.radius;
-            } else if wall.top {
-                self.position.y = SRC_HEIGHT as f32 - self.radius;
-            }
-        }
+    pub fn overlaps(&self, other: &BallObject) -> bool {
+        (other.position - self.position).length() < self.radius + other.radius
     }
 
-    pub fn check_ball_square_collision() {
-        // for ball1 in &mut ball_objects {
-        //     for square in &mut square_objects {
-        //         let (collided, side_index, ball_pos) = check_ball_square_collision(
-        //             ball.position,
-        //             ball.radius,
-        //             square.position,
-        //             square.size,
-        //             square.rotation,
-        //         );
-        //         if collided {
-        //             let normal = square.get_normal_relative_to(side_index);
-        //
-        //             ball.position = ball_pos;
-        //
-        //             ball.velocity -= 2.0 * ball.velocity.dot(normal) * normal;
-        //             // ball.velocity *= 0.9; //Damping
-        //
-        //             normal_square = Vec3::new(ball.velocity.x, ball.velocity.y, 0.0);
-        //
-        //             square.color = Vec3::new(1.0, 0.5, 0.0);
-        //             break;
-        //         } else {
-        //             square.color = Vec3::new(0.3, 0.8, 1.0);
-        //         }
-        //     }
-        // }
+    /// Absorbs `other` conserving mass and momentum. The merged ball keeps the combined area,
+    /// unless it is a black hole, whose size is its horizon.
+    pub fn merge(&mut self, other: &BallObject, gravity: &Gravity) {
+        let total_mass = self.mass + other.mass;
+        let t = if total_mass > 0.0 {
+            other.mass / total_mass
+        } else {
+            0.5
+        };
+
+        self.position = self.position.lerp(other.position, t);
+        self.previous_position = self.previous_position.lerp(other.previous_position, t);
+        self.velocity = self.velocity.lerp(other.velocity, t);
+        self.radius = (self.radius.powi(2) + other.radius.powi(2)).sqrt();
+        self.color = self.color.blend(other.color, t);
+        self.mass = total_mass;
+        self.has_collision |= other.has_collision;
+        self.has_gravity |= other.has_gravity;
+        self.is_black_hole |= other.is_black_hole;
+        if self.is_black_hole {
+            self.radius = gravity.schwarzschild_radius(self.mass);
+        }
     }
 
     pub fn check_ball_ball_collision(&mut self, ball2: &mut BallObject) {
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+/// Uniform grid keyed by cell coordinates. Bodies are inserted into every cell
+/// their bounding box touches, so large bodies work with a small cell size.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialHash {
+            cell_size: cell_size.max(1.0),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, index: usize, position: Vec3, radius: f32) {
+        let (min_x, min_y) = self.cell(position.x - radius, position.y - radius);
+        let (max_x, max_y) = self.cell(position.x + radius, position.y + radius);
+
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Pairs `(i, j)` with `i < j` whose bounding boxes overlap, each reported once.
+    pub fn candidate_pairs(&self, positions: &[Vec3], radii: &[f32]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+
+        for bodies in self.cells.values() {
+            for (a, &i) in bodies.iter().enumerate() {
+                for &j in &bodies[a + 1..] {
+                    let (i, j) = if i < j { (i, j) } else { (j, i) };
+                    let reach = radii[i] + radii[j];
+                    let delta = positions[j] - positions[i];
+                    if delta.x.abs() <= reach && delta.y.abs() <= reach {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
@@ -0,0 +1,106 @@
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::world::World;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Follow {
+    Free,
+    /// Keeps the ball with this id centred.
+    Body(u64),
+    /// Keeps the centre of mass of all bodies centred.
+    Barycentre,
+}
+
+impl Follow {
+    pub fn name(self) -> &'static str {
+        match self {
+            Follow::Free => "Free",
+            Follow::Body(_) => "Body",
+            Follow::Barycentre => "Barycentre",
+        }
+    }
+}
+
+/// 2D view onto the world. Screen coordinates are window coordinates with y pointing down,
+/// as GLFW reports the cursor.
+pub struct Camera {
+    /// World position shown in the middle of the window
+    pub center: Vec3,
+    /// Screen units per world unit
+    pub zoom: f32,
+    /// Window size in screen units
+    pub viewport: Vec2,
+    pub follow: Follow,
+}
+
+impl Camera {
+    /// Camera showing the world box of the given size, which starts at the origin.
+    pub fn new(width: f32, height: f32) -> Self {
+        Camera {
+            center: Vec3::new(width / 2.0, height / 2.0, 0.0),
+            zoom: 1.0,
+            viewport: Vec2::new(width, height),
+            follow: Follow::Free,
+        }
+    }
+
+    /// Changes the window size. A free camera keeps the bottom-left corner in place, so the
+    /// world origin stays where the world bounds start.
+    pub fn resize(&mut self, viewport: Vec2) {
+        if self.follow == Follow::Free {
+            let shift = (viewport - self.viewport) / (2.0 * self.zoom);
+            self.center += shift.extend(0.0);
+        }
+        self.viewport = viewport;
+    }
+
+    pub fn projection(&self) -> Mat4 {
+        let half = self.viewport * 0.5 / self.zoom;
+        Mat4::orthographic_rh_gl(
+            self.center.x - half.x,
+            self.center.x + half.x,
+            self.center.y - half.y,
+            self.center.y + half.y,
+            -1.0,
+            1.0,
+        )
+    }
+
+    /// Centres the followed target. A followed body that merges or is swallowed hands over to
+    /// the one it went into, and the camera drops back to `Follow::Free` once it is gone.
+    pub fn track(&mut self, world: &World, alpha: f32) {
+        let target = match self.follow {
+            Follow::Free => None,
+            Follow::Body(id) => {
+                let id = world.survivor(id);
+                self.follow = Follow::Body(id);
+                let ball = world.balls.iter().find(|b| b.id == id);
+                if ball.is_none() {
+                    self.follow = Follow::Free;
+                }
+                ball.map(|b| b.previous_position.lerp(b.position, alpha))
+            }
+            Follow::Barycentre => world.barycentre(alpha),
+        };
+        if let Some(target) = target {
+            self.center = target;
+        }
+    }
+
+    pub fn screen_to_world(&self, screen: Vec2) -> Vec3 {
+        let offset = screen - self.viewport * 0.5;
+        self.center + Vec3::new(offset.x, -offset.y, 0.0) / self.zoom
+    }
+
+    /// Moves the view so the world follows a cursor that moved by `screen_delta`.
+    pub fn pan(&mut self, screen_delta: Vec2) {
+        self.center -= Vec3::new(screen_delta.x, -screen_delta.y, 0.0) / self.zoom;
+    }
+
+    /// Zooms by `factor` while keeping the world point under `screen` in place.
+    pub fn zoom_at(&mut self, screen: Vec2, factor: f32) {
+        let anchor = self.screen_to_world(screen);
+        self.zoom = (self.zoom * factor).clamp(0.01, 100.0);
+        self.center += anchor - self.screen_to_world(screen);
+    }
+}
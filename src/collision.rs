@@ -1,4 +1,61 @@
-use glam::Vec3;
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionResponse {
+    Bounce,
+    /// Colliding bodies merge into one, as in planetesimal accretion.
+    Merge,
+}
+
+impl CollisionResponse {
+    pub fn name(self) -> &'static str {
+        match self {
+            CollisionResponse::Bounce => "Bounce",
+            CollisionResponse::Merge => "Merge",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CollisionResponse::Bounce => CollisionResponse::Merge,
+            CollisionResponse::Merge => CollisionResponse::Bounce,
+        }
+    }
+}
+
+/// What happens to bodies at the screen edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Boundary {
+    /// Bounce back, keeping `World::restitution` of the speed.
+    Reflect,
+    /// Leave on one side and come back on the opposite one.
+    Wrap,
+    /// Bodies that leave the screen are deleted.
+    Absorb,
+    /// No walls at all.
+    Open,
+}
+
+impl Boundary {
+    pub fn name(self) -> &'static str {
+        match self {
+            Boundary::Reflect => "Reflect",
+            Boundary::Wrap => "Wrap",
+            Boundary::Absorb => "Absorb",
+            Boundary::Open => "Open",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Boundary::Reflect => Boundary::Wrap,
+            Boundary::Wrap => Boundary::Absorb,
+            Boundary::Absorb => Boundary::Open,
+            Boundary::Open => Boundary::Reflect,
+        }
+    }
+}
 
 //OBB
 pub fn check_ball_square_collision(
@@ -22,21 +79,33 @@ pub fn check_ball_square_collision(
     let diff = local_ball - Vec3::new(closest_x, closest_y, 0.0);
     let dist_sq = diff.length_squared();
 
+    // Edge indices as in SquareObject: 0 right, 1 bottom, 2 left, 3 top
     let mut side: usize = 0;
 
     if dist_sq < ball_radius * ball_radius {
         if diff.x.abs() > diff.y.abs() {
             side = if diff.x > 0. { 0 } else { 2 };
         } else {
-            side = if diff.y > 0. { 1 } else { 3 };
+            side = if diff.y > 0. { 3 } else { 1 };
         }
 
-        let dist = dist_sq.sqrt();
-        let penetration = ball_radius - dist;
-        let normal = if dist != 0.0 { diff / dist } else { Vec3::X };
-
-        let correction_local = normal * penetration;
-        let new_local_ball = local_ball + correction_local;
+        let new_local_ball = if dist_sq > 0.0 {
+            let dist = dist_sq.sqrt();
+            local_ball + diff / dist * (ball_radius - dist)
+        } else {
+            // Centre inside: leave through the side it is least deep behind
+            let depths = [
+                half_size - local_ball.x,
+                half_size + local_ball.y,
+                half_size + local_ball.x,
+                half_size - local_ball.y,
+            ];
+            side = (0..4)
+                .min_by(|&a, &b| depths[a].total_cmp(&depths[b]))
+                .unwrap();
+            let normal = [Vec3::X, -Vec3::Y, -Vec3::X, Vec3::Y][side];
+            local_ball + normal * (depths[side] + ball_radius)
+        };
 
         let world_x = cos_r * new_local_ball.x - sin_r * new_local_ball.y + square_pos.x;
         let world_y = sin_r * new_local_ball.x + cos_r * new_local_ball.y + square_pos.y;
@@ -48,6 +117,40 @@ pub fn check_ball_square_collision(
     (false, side, ball_pos)
 }
 
+//Capsule
+pub fn check_ball_segment_collision(
+    ball_pos: Vec3,
+    ball_radius: f32,
+    start: Vec3,
+    end: Vec3,
+) -> (bool, Vec3, Vec3) {
+    let edge = end - start;
+    let length_sq = edge.length_squared();
+    let t = if length_sq > 0.0 {
+        ((ball_pos - start).dot(edge) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let closest = start + edge * t;
+
+    let diff = ball_pos - closest;
+    let dist_sq = diff.length_squared();
+
+    if dist_sq < ball_radius * ball_radius {
+        let dist = dist_sq.sqrt();
+        let normal = if dist != 0.0 {
+            diff / dist
+        } else {
+            Vec3::new(-edge.y, edge.x, 0.0).normalize_or(Vec3::Y)
+        };
+
+        let new_ball_pos = closest + normal * ball_radius;
+        return (true, normal, new_ball_pos);
+    }
+
+    (false, Vec3::ZERO, ball_pos)
+}
+
 pub struct WallCollision {
     pub left: bool,
     pub right: bool,
@@ -83,3 +186,193 @@ pub fn check_wall_collision(
 
     collision
 }
+
+/// Whole circle is past one of the screen edges.
+pub fn is_outside(position: Vec3, radius: f32, screen_width: f32, screen_height: f32) -> bool {
+    position.x + radius < 0.0
+        || position.x - radius > screen_width
+        || position.y + radius < 0.0
+        || position.y - radius > screen_height
+}
+
+pub fn wrap_position(position: Vec3, screen_width: f32, screen_height: f32) -> Vec3 {
+    Vec3::new(
+        position.x.rem_euclid(screen_width),
+        position.y.rem_euclid(screen_height),
+        position.z,
+    )
+}
+
+/// Overlap found by a separating axis test.
+pub struct Contact {
+    /// Points from the first shape into the second
+    pub normal: Vec2,
+    pub depth: f32,
+    pub point: Vec2,
+}
+
+fn project(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+    vertices
+        .iter()
+        .map(|v| v.dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), p| {
+            (min.min(p), max.max(p))
+        })
+}
+
+// Vertex furthest along `direction`
+fn support(vertices: &[Vec2], direction: Vec2) -> Vec2 {
+    *vertices
+        .iter()
+        .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+        .unwrap()
+}
+
+fn centroid(vertices: &[Vec2]) -> Vec2 {
+    vertices.iter().copied().sum::<Vec2>() / vertices.len() as f32
+}
+
+//SAT
+pub fn check_polygon_polygon_collision(
+    a: &[Vec2],
+    a_normals: &[Vec2],
+    b: &[Vec2],
+    b_normals: &[Vec2],
+) -> Option<Contact> {
+    let mut depth = f32::MAX;
+    let mut normal = Vec2::ZERO;
+
+    for &axis in a_normals.iter().chain(b_normals) {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        let overlap = a_max.min(b_max) - a_min.max(b_min);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if overlap < depth {
+            depth = overlap;
+            normal = axis;
+        }
+    }
+
+    if (centroid(b) - centroid(a)).dot(normal) < 0.0 {
+        normal = -normal;
+    }
+
+    // Halfway between the deepest points of both shapes
+    let point = (support(a, normal) + support(b, -normal)) * 0.5;
+
+    Some(Contact {
+        normal,
+        depth,
+        point,
+    })
+}
+
+//SAT, circle as the second shape
+pub fn check_polygon_circle_collision(
+    polygon: &[Vec2],
+    normals: &[Vec2],
+    center: Vec2,
+    radius: f32,
+) -> Option<Contact> {
+    let closest = *polygon.iter().min_by(|a, b| {
+        a.distance_squared(center)
+            .total_cmp(&b.distance_squared(center))
+    })?;
+    let vertex_axis = (center - closest).normalize_or_zero();
+
+    let mut depth = f32::MAX;
+    let mut normal = Vec2::ZERO;
+
+    for &axis in normals.iter().chain([vertex_axis].iter()) {
+        if axis == Vec2::ZERO {
+            continue;
+        }
+        let (p_min, p_max) = project(polygon, axis);
+        let c = center.dot(axis);
+        let overlap = p_max.min(c + radius) - p_min.max(c - radius);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if overlap < depth {
+            depth = overlap;
+            normal = axis;
+        }
+    }
+
+    if (center - centroid(polygon)).dot(normal) < 0.0 {
+        normal = -normal;
+    }
+
+    Some(Contact {
+        normal,
+        depth,
+        point: center - normal * radius,
+    })
+}
+
+/// Rigid body state needed to resolve a contact. Zero inverse mass means immovable,
+/// zero inverse inertia means the body doesn't spin.
+pub struct ContactBody {
+    pub center: Vec2,
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+    pub inverse_mass: f32,
+    pub inverse_inertia: f32,
+}
+
+impl ContactBody {
+    pub fn fixed(center: Vec2) -> Self {
+        ContactBody {
+            center,
+            velocity: Vec2::ZERO,
+            angular_velocity: 0.0,
+            inverse_mass: 0.0,
+            inverse_inertia: 0.0,
+        }
+    }
+
+    fn point_velocity(&self, point: Vec2) -> Vec2 {
+        self.velocity + (point - self.center).perp() * self.angular_velocity
+    }
+}
+
+/// Separates `a` and `b` and applies an impulse at the contact point, so off-center hits
+/// make the bodies spin.
+pub fn resolve_contact(
+    contact: &Contact,
+    a: &mut ContactBody,
+    b: &mut ContactBody,
+    restitution: f32,
+) {
+    let total_inverse_mass = a.inverse_mass + b.inverse_mass;
+    if total_inverse_mass == 0.0 {
+        return;
+    }
+
+    let n = contact.normal;
+    let r_a = contact.point - a.center;
+    let r_b = contact.point - b.center;
+
+    a.center -= n * contact.depth * (a.inverse_mass / total_inverse_mass);
+    b.center += n * contact.depth * (b.inverse_mass / total_inverse_mass);
+
+    let vel_along_normal =
+        (b.point_velocity(contact.point) - a.point_velocity(contact.point)).dot(n);
+    if vel_along_normal > 0.0 {
+        return;
+    }
+
+    let ra_n = r_a.perp_dot(n);
+    let rb_n = r_b.perp_dot(n);
+    let denominator =
+        total_inverse_mass + ra_n * ra_n * a.inverse_inertia + rb_n * rb_n * b.inverse_inertia;
+
+    let impulse = -(1.0 + restitution) * vel_along_normal / denominator;
+
+    a.velocity -= n * impulse * a.inverse_mass;
+    a.angular_velocity -= ra_n * impulse * a.inverse_inertia;
+    b.velocity += n * impulse * b.inverse_mass;
+    b.angular_velocity += rb_n * impulse * b.inverse_inertia;
+}
@@ -0,0 +1,87 @@
+use glam::Vec3;
+
+use crate::{ball_obj::BallObject, gravity::Gravity, polygon_obj::PolygonObject};
+
+/// Conserved quantities of a set of bodies.
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostics {
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+    pub momentum: Vec3,
+    /// z component around the world origin
+    pub angular_momentum: f32,
+}
+
+impl Diagnostics {
+    pub fn measure(balls: &[BallObject], polygons: &[PolygonObject], gravity: &Gravity) -> Self {
+        let mut kinetic_energy = 0.0;
+        let mut potential_energy = 0.0;
+        let mut momentum = Vec3::ZERO;
+        let mut angular_momentum = 0.0;
+
+        // Position, velocity, mass and horizon of every body
+        let bodies: Vec<(Vec3, Vec3, f32, f32)> = balls
+            .iter()
+            .map(|b| (b.position, b.velocity, b.mass, b.horizon(gravity)))
+            .chain(
+                polygons
+                    .iter()
+                    .map(|p| (p.position, p.velocity, p.mass, 0.0)),
+            )
+            .collect();
+
+        for (i, &(position, velocity, mass, horizon)) in bodies.iter().enumerate() {
+            kinetic_energy += 0.5 * mass * velocity.length_squared();
+            momentum += mass * velocity;
+            angular_momentum += mass * position.truncate().perp_dot(velocity.truncate());
+
+            for &(other_position, _, other_mass, other_horizon) in &bodies[i + 1..] {
+                potential_energy += gravity.potential_energy(
+                    other_position - position,
+                    mass,
+                    other_mass,
+                    horizon.max(other_horizon),
+                );
+            }
+        }
+
+        // Spin of the rigid bodies
+        for polygon in polygons {
+            kinetic_energy += 0.5 * polygon.inertia * polygon.angular_velocity.powi(2);
+            angular_momentum += polygon.inertia * polygon.angular_velocity;
+        }
+
+        Diagnostics {
+            kinetic_energy,
+            potential_energy,
+            momentum,
+            angular_momentum,
+        }
+    }
+
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.potential_energy
+    }
+
+    /// Relative change of the total energy since `start`.
+    pub fn energy_drift(&self, start: &Diagnostics) -> f32 {
+        relative_drift(self.total_energy(), start.total_energy())
+    }
+
+    pub fn momentum_drift(&self, start: &Diagnostics) -> f32 {
+        (self.momentum - start.momentum).length()
+    }
+
+    /// Relative change of the angular momentum since `start`.
+    pub fn angular_momentum_drift(&self, start: &Diagnostics) -> f32 {
+        relative_drift(self.angular_momentum, start.angular_momentum)
+    }
+}
+
+fn relative_drift(value: f32, start: f32) -> f32 {
+    if start == 0.0 {
+        value - start
+    } else {
+        (value - start) / start.abs()
+    }
+}
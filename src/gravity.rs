@@ -0,0 +1,121 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+// Scaled so a mass of 5000 has an event horizon of 100 units
+pub const SPEED_OF_LIGHT: f32 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GravitySolver {
+    DirectSum,
+    BarnesHut,
+}
+
+impl GravitySolver {
+    pub fn name(self) -> &'static str {
+        match self {
+            GravitySolver::DirectSum => "Direct sum",
+            GravitySolver::BarnesHut => "Barnes-Hut",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GravitySolver::DirectSum => GravitySolver::BarnesHut,
+            GravitySolver::BarnesHut => GravitySolver::DirectSum,
+        }
+    }
+}
+
+/// Potential a body pulls others with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Potential {
+    Newtonian,
+    /// Pseudo-Newtonian -GM / (r - r_s). Has an innermost stable circular orbit at 3 r_s
+    /// and makes orbits precess.
+    PaczynskiWiita,
+}
+
+impl Potential {
+    pub fn name(self) -> &'static str {
+        match self {
+            Potential::Newtonian => "Newtonian",
+            Potential::PaczynskiWiita => "Paczynski-Wiita",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Potential::Newtonian => Potential::PaczynskiWiita,
+            Potential::PaczynskiWiita => Potential::Newtonian,
+        }
+    }
+}
+
+/// Newtonian gravity with Plummer softening.
+#[derive(Debug, Clone, Copy)]
+pub struct Gravity {
+    pub g: f32,
+    /// Smooths the force below this distance instead of letting it blow up.
+    pub softening: f32,
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity {
+            g: 100.0,
+            softening: 5.0,
+        }
+    }
+}
+
+impl Gravity {
+    /// Acceleration towards a point mass `direction` away.
+    pub fn point_acceleration(&self, direction: Vec3, mass: f32) -> Vec3 {
+        //a = G * m2 * d / (r^2 + eps^2)^(3/2)
+        let softened = direction.length_squared() + self.softening.powi(2);
+        if softened == 0.0 {
+            return Vec3::ZERO;
+        }
+        direction * self.g * mass / softened.powf(1.5)
+    }
+
+    /// Like `point_acceleration`, but for a Paczynski-Wiita source with the given
+    /// Schwarzschild radius. A `horizon` of 0 falls back to Newtonian gravity.
+    pub fn acceleration(&self, direction: Vec3, mass: f32, horizon: f32) -> Vec3 {
+        if horizon <= 0.0 {
+            return self.point_acceleration(direction, mass);
+        }
+        //a = G * m2 / (r - r_s)^2
+        let r = direction.length();
+        if r == 0.0 {
+            return Vec3::ZERO;
+        }
+        let gap = self.horizon_gap(r, horizon);
+        direction / r * self.g * mass / gap.powi(2)
+    }
+
+    /// Potential energy of two point masses `direction` apart. `horizon` as in `acceleration`.
+    pub fn potential_energy(&self, direction: Vec3, mass1: f32, mass2: f32, horizon: f32) -> f32 {
+        if horizon > 0.0 {
+            //U = -G * m1 * m2 / (r - r_s)
+            let gap = self.horizon_gap(direction.length(), horizon);
+            return -self.g * mass1 * mass2 / gap;
+        }
+        //U = -G * m1 * m2 / sqrt(r^2 + eps^2)
+        let softened = (direction.length_squared() + self.softening.powi(2)).sqrt();
+        if softened == 0.0 {
+            return 0.0;
+        }
+        -self.g * mass1 * mass2 / softened
+    }
+
+    // Distance outside the horizon, kept away from the singularity at r = r_s
+    fn horizon_gap(&self, r: f32, horizon: f32) -> f32 {
+        (r - horizon).max(self.softening).max(1e-3)
+    }
+
+    /// r = 2GM / c^2
+    pub fn schwarzschild_radius(&self, mass: f32) -> f32 {
+        2.0 * self.g * mass / SPEED_OF_LIGHT.powi(2)
+    }
+}
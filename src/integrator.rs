@@ -0,0 +1,104 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Integrator {
+    SemiImplicitEuler,
+    VelocityVerlet,
+    Leapfrog,
+    Rk4,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 4] = [
+        Integrator::SemiImplicitEuler,
+        Integrator::VelocityVerlet,
+        Integrator::Leapfrog,
+        Integrator::Rk4,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::SemiImplicitEuler => "Semi-implicit Euler",
+            Integrator::VelocityVerlet => "Velocity Verlet",
+            Integrator::Leapfrog => "Leapfrog",
+            Integrator::Rk4 => "RK4",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&i| i == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Advances `positions` and `velocities` by `delta_time`.
+    /// `acceleration` maps a set of positions to the acceleration of every body.
+    pub fn step<F>(
+        self,
+        positions: &mut [Vec3],
+        velocities: &mut [Vec3],
+        delta_time: f32,
+        acceleration: F,
+    ) where
+        F: Fn(&[Vec3]) -> Vec<Vec3>,
+    {
+        match self {
+            Integrator::SemiImplicitEuler => {
+                let a = acceleration(positions);
+                for i in 0..positions.len() {
+                    velocities[i] += a[i] * delta_time;
+                    positions[i] += velocities[i] * delta_time;
+                }
+            }
+            Integrator::VelocityVerlet => {
+                let a0 = acceleration(positions);
+                for i in 0..positions.len() {
+                    positions[i] +=
+                        velocities[i] * delta_time + 0.5 * a0[i] * delta_time * delta_time;
+                }
+                let a1 = acceleration(positions);
+                for i in 0..positions.len() {
+                    velocities[i] += 0.5 * (a0[i] + a1[i]) * delta_time;
+                }
+            }
+            // Drift-kick-drift
+            Integrator::Leapfrog => {
+                let half = delta_time * 0.5;
+                for i in 0..positions.len() {
+                    positions[i] += velocities[i] * half;
+                }
+                let a = acceleration(positions);
+                for i in 0..positions.len() {
+                    velocities[i] += a[i] * delta_time;
+                    positions[i] += velocities[i] * half;
+                }
+            }
+            Integrator::Rk4 => {
+                let x0 = positions.to_vec();
+                let v0 = velocities.to_vec();
+                let offset = |base: &[Vec3], d: &[Vec3], h: f32| -> Vec<Vec3> {
+                    base.iter().zip(d).map(|(b, d)| *b + *d * h).collect()
+                };
+
+                let k1x = v0.clone();
+                let k1v = acceleration(&x0);
+
+                let k2x = offset(&v0, &k1v, delta_time * 0.5);
+                let k2v = acceleration(&offset(&x0, &k1x, delta_time * 0.5));
+
+                let k3x = offset(&v0, &k2v, delta_time * 0.5);
+                let k3v = acceleration(&offset(&x0, &k2x, delta_time * 0.5));
+
+                let k4x = offset(&v0, &k3v, delta_time);
+                let k4v = acceleration(&offset(&x0, &k3x, delta_time));
+
+                for i in 0..positions.len() {
+                    positions[i] =
+                        x0[i] + (k1x[i] + 2.0 * k2x[i] + 2.0 * k3x[i] + k4x[i]) * delta_time / 6.0;
+                    velocities[i] =
+                        v0[i] + (k1v[i] + 2.0 * k2v[i] + 2.0 * k3v[i] + k4v[i]) * delta_time / 6.0;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use glam::{Mat4, Vec2, Vec4};
+
+use blackhole::world::World;
+
+const MAX_LENSES: usize = 8;
+// Einstein radius relative to the event horizon
+const EINSTEIN_SCALE: f32 = 1.5;
+
+/// Screen-space gravitational lensing. The scene is rendered into an offscreen
+/// texture between `begin` and `end`, which then draws it distorted to the screen.
+pub struct LensingPass {
+    background_program: u32,
+    lens_program: u32,
+    width: i32,
+    height: i32,
+    fbo: u32,
+    texture: u32,
+    vao: u32,
+    vbo: u32,
+}
+
+/// A lens in screen pixels.
+pub struct Lens {
+    pub position: Vec2,
+    pub einstein_radius: f32,
+    pub horizon_radius: f32,
+}
+
+impl LensingPass {
+    pub fn new(background_program: u32, lens_program: u32, width: i32, height: i32) -> Self {
+        let mut pass = LensingPass {
+            background_program,
+            lens_program,
+            width,
+            height,
+            fbo: 0,
+            texture: 0,
+            vao: 0,
+            vbo: 0,
+        };
+
+        pass.mesh();
+        pass.create_target();
+
+        pass
+    }
+
+    /// Recreates the offscreen texture for a new framebuffer size in pixels.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.texture);
+        }
+        self.width = width;
+        self.height = height;
+        self.create_target();
+    }
+
+    pub fn begin(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width, self.height);
+
+            gl::UseProgram(self.background_program);
+            self.set_resolution(self.background_program);
+            self.draw_quad();
+        }
+    }
+
+    /// Lenses for every black hole in `world`, as seen through `projection`.
+    pub fn lenses(&self, world: &World, alpha: f32, projection: &Mat4) -> Vec<Lens> {
+        let size = Vec2::new(self.width as f32, self.height as f32);
+        let pixels_per_unit = projection.x_axis.x * size.x * 0.5;
+
+        world
+            .balls
+            .iter()
+            .filter(|b| b.is_black_hole)
+            .map(|hole| {
+                let position = hole.previous_position.lerp(hole.position, alpha);
+                let ndc = projection.project_point3(position).truncate();
+                let horizon = world.gravity.schwarzschild_radius(hole.mass) * pixels_per_unit;
+                Lens {
+                    position: (ndc * 0.5 + 0.5) * size,
+                    einstein_radius: horizon * EINSTEIN_SCALE,
+                    horizon_radius: horizon,
+                }
+            })
+            .collect()
+    }
+
+    pub fn end(&self, lenses: &[Lens]) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            gl::UseProgram(self.lens_program);
+            self.set_resolution(self.lens_program);
+
+            let count = lenses.len().min(MAX_LENSES);
+            let data: Vec<Vec4> = lenses[..count]
+                .iter()
+                .map(|l| {
+                    Vec4::new(
+                        l.position.x,
+                        l.position.y,
+                        l.einstein_radius,
+                        l.horizon_radius,
+                    )
+                })
+                .collect();
+
+            let count_loc = gl::GetUniformLocation(self.lens_program, c"lensCount".as_ptr());
+            gl::Uniform1i(count_loc, count as i32);
+            if count > 0 {
+                let lenses_loc = gl::GetUniformLocation(self.lens_program, c"lenses".as_ptr());
+                gl::Uniform4fv(lenses_loc, count as i32, data.as_ptr() as *const f32);
+            }
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            let scene_loc = gl::GetUniformLocation(self.lens_program, c"scene".as_ptr());
+            gl::Uniform1i(scene_loc, 0);
+
+            self.draw_quad();
+        }
+    }
+
+    unsafe fn set_resolution(&self, program: u32) {
+        unsafe {
+            let loc = gl::GetUniformLocation(program, c"resolution".as_ptr());
+            gl::Uniform2f(loc, self.width as f32, self.height as f32);
+        }
+    }
+
+    unsafe fn draw_quad(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    fn create_target(&mut self) {
+        unsafe {
+            gl::GenFramebuffers(1, &mut self.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+
+            gl::GenTextures(1, &mut self.texture);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as i32,
+                self.width,
+                self.height,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            // Rays bent off screen pick up the mirrored scene instead of a smeared edge
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::MIRRORED_REPEAT as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::MIRRORED_REPEAT as i32,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.texture,
+                0,
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    fn mesh(&mut self) {
+        let vertices: [f32; 12] = [
+            -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0,
+        ];
+        unsafe {
+            gl::GenVertexArrays(1, &mut self.vao);
+            gl::GenBuffers(1, &mut self.vbo);
+
+            gl::BindVertexArray(self.vao);
+
+            //VBO
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(&vertices) as isize,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::STATIC_DRAW,
+            );
+
+            //position
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (2 * std::mem::size_of::<f32>()) as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+    }
+}
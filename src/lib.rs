@@ -0,0 +1,16 @@
+//! Simulation without any GL or window dependency, driven and drawn by the binary.
+
+pub mod ball_obj;
+pub mod broadphase;
+pub mod camera;
+pub mod collision;
+pub mod diagnostics;
+pub mod gravity;
+pub mod integrator;
+pub mod polygon_obj;
+pub mod quadtree;
+pub mod recording;
+pub mod scene;
+pub mod segment_obj;
+pub mod square_obj;
+pub mod world;
@@ -65,7 +65,7 @@ impl LineRenderer {
 
             let color_name = std::ffi::CString::new("objectColor").unwrap();
             let color_loc = gl::GetUniformLocation(shader_program, color_name.as_ptr());
-            gl::Uniform3f(color_loc, color.x, color.y, color.z);
+            gl::Uniform4f(color_loc, color.x, color.y, color.z, 1.0);
 
             let vertices: [f32; 6] = [start.x, start.y, start.z, end.x, end.y, end.z];
 
@@ -83,6 +83,52 @@ impl LineRenderer {
         }
     }
 
+    /// Draws connected segments through `points`.
+    pub fn draw_polyline(
+        &self,
+        points: &[Vec3],
+        color: Vec3,
+        shader_program: u32,
+        projection: &Mat4,
+    ) {
+        if points.len() < 2 {
+            return;
+        }
+        unsafe {
+            gl::UseProgram(shader_program);
+
+            let transform = *projection;
+
+            let transform_name = std::ffi::CString::new("transform").unwrap();
+            let transform_loc = gl::GetUniformLocation(shader_program, transform_name.as_ptr());
+            gl::UniformMatrix4fv(
+                transform_loc,
+                1,
+                gl::FALSE,
+                &transform as *const Mat4 as *const f32,
+            );
+
+            let color_name = std::ffi::CString::new("objectColor").unwrap();
+            let color_loc = gl::GetUniformLocation(shader_program, color_name.as_ptr());
+            gl::Uniform4f(color_loc, color.x, color.y, color.z, 1.0);
+
+            let vertices: Vec<f32> = points.iter().flat_map(|p| [p.x, p.y, p.z]).collect();
+
+            // Reallocated per call, draw_line only ever needs the first two vertices
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::DYNAMIC_DRAW,
+            );
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::LINE_STRIP, 0, points.len() as i32);
+            gl::BindVertexArray(0);
+        }
+    }
+
     pub fn draw_vector(
         &self,
         origin: Vec3,
@@ -1,87 +1,94 @@
 extern crate glfw;
 use glfw::{Action, Context, Key, MouseButton, fail_on_errors};
 extern crate gl;
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
 use std::{ffi::CString, fs};
 
-// mod square_obj;
-// use square_obj::SquareObject;
+use blackhole::{
+    ball_obj::{BallObject, Color},
+    camera::{Camera, Follow},
+    collision::Boundary,
+    gravity::{GravitySolver, Potential},
+    polygon_obj::PolygonObject,
+    recording::{Command, Recording, Replay, Session},
+    scene::Scene,
+    square_obj::SquareObject,
+    world::World,
+};
 
 mod render_text;
 use render_text::TextRenderer;
 
-mod ball_obj;
-use ball_obj::BallObject;
-
-mod collision;
+mod lensing;
+use lensing::LensingPass;
 
 mod line_renderer;
 use line_renderer::LineRenderer;
 
-use crate::ball_obj::Color;
+mod trail_renderer;
+use trail_renderer::TrailRenderer;
+
+mod world_renderer;
+use world_renderer::WorldRenderer;
+
+const RECORDING_PATH: &str = "recording.toml";
 
+// Initial window size. The world follows the window when it is resized.
 const SRC_WIDTH: u32 = 800;
 const SRC_HEIGHT: u32 = 600;
+// Launch speed per pixel of slingshot drag
+const SLINGSHOT_SCALE: f32 = 1.0;
+const TRAJECTORY_PREVIEW_SECONDS: f32 = 3.0;
+// Measuring diagnostics is O(n^2), so the HUD refreshes them only this often
+const DIAGNOSTICS_INTERVAL: f32 = 0.25;
 
 fn load_shader_source(path: &str) -> CString {
     let source = fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to read file"));
     CString::new(source).unwrap()
 }
 
-fn window() {
-    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-
-    let (mut window, events) = glfw
-        .create_window(
-            SRC_WIDTH,
-            SRC_HEIGHT,
-            "Test Title",
-            glfw::WindowMode::Windowed,
-        )
-        .expect("Failed to create Window");
-    window.make_current();
-    glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-    gl::load_with(|s| {
-        window
-            .get_proc_address(s)
-            .map_or(std::ptr::null(), |p| p as *const _)
-    });
-    window.set_mouse_button_polling(true);
-    window.set_scroll_polling(true);
-    window.set_key_polling(true);
-
-    let vertex_source = load_shader_source("./shader/vertex.glsl");
-
-    let fragment_source = load_shader_source("./shader/fragment.glsl");
-
-    let shader_program = unsafe {
-        // Vertex shader
-        let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-        let vertex_ptr = vertex_source.as_ptr();
-        gl::ShaderSource(vertex_shader, 1, &vertex_ptr, std::ptr::null());
-        gl::CompileShader(vertex_shader);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpawnMode {
+    /// Press, drag and release. The drag sets the launch velocity.
+    Slingshot,
+    /// Click to place a ball on an orbit around the dominant attractor.
+    Orbit,
+    /// Press, drag and release to draw a static wall between both points.
+    Wall,
+}
 
-        // Fragment shader
-        let fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-        let fragment_ptr = fragment_source.as_ptr();
-        gl::ShaderSource(fragment_shader, 1, &fragment_ptr, std::ptr::null());
-        gl::CompileShader(fragment_shader);
+impl SpawnMode {
+    fn name(self) -> &'static str {
+        match self {
+            SpawnMode::Slingshot => "Slingshot",
+            SpawnMode::Orbit => "Orbit",
+            SpawnMode::Wall => "Wall",
+        }
+    }
 
-        // Shader program
-        let program = gl::CreateProgram();
-        gl::AttachShader(program, vertex_shader);
-        gl::AttachShader(program, fragment_shader);
-        gl::LinkProgram(program);
-        gl::DeleteShader(vertex_shader);
-        gl::DeleteShader(fragment_shader);
+    fn next(self) -> Self {
+        match self {
+            SpawnMode::Slingshot => SpawnMode::Orbit,
+            SpawnMode::Orbit => SpawnMode::Wall,
+            SpawnMode::Wall => SpawnMode::Slingshot,
+        }
+    }
+}
 
-        program
-    };
-    let vertex_source = load_shader_source("./shader/text_vertex.glsl");
+/// In-memory copy of the simulation and the spawn settings, restored with Shift+F1-F4.
+struct Snapshot {
+    world: World,
+    spawn_mode: SpawnMode,
+    eccentricity: f32,
+    radius: f32,
+    mass: f32,
+}
 
-    let fragment_source = load_shader_source("./shader/text_fragment.glsl");
+fn create_shader_program(vertex_path: &str, fragment_path: &str) -> u32 {
+    let vertex_source = load_shader_source(vertex_path);
+    let fragment_source = load_shader_source(fragment_path);
 
-    let text_shader_program = unsafe {
+    unsafe {
         // Vertex shader
         let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
         let vertex_ptr = vertex_source.as_ptr();
@@ -103,16 +110,54 @@ fn window() {
         gl::DeleteShader(fragment_shader);
 
         program
-    };
+    }
+}
 
-    // let square = SquareObject::new(
-    //     Vec3::new(200.0, 300.0, 0.0),
-    //     std::f32::consts::PI / 6.,
-    //     100.0,
-    //     Vec3::new(0.5, 0.5, 0.2),
-    // );
+fn window() {
+    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
+    glfw.window_hint(glfw::WindowHint::Resizable(true));
+
+    let (mut window, events) = glfw
+        .create_window(
+            SRC_WIDTH,
+            SRC_HEIGHT,
+            "Test Title",
+            glfw::WindowMode::Windowed,
+        )
+        .expect("Failed to create Window");
+    window.make_current();
+    glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
+    gl::load_with(|s| {
+        window
+            .get_proc_address(s)
+            .map_or(std::ptr::null(), |p| p as *const _)
+    });
+    window.set_mouse_button_polling(true);
+    window.set_scroll_polling(true);
+    window.set_key_polling(true);
+    window.set_size_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let shader_program = create_shader_program("./shader/vertex.glsl", "./shader/fragment.glsl");
+    let text_shader_program =
+        create_shader_program("./shader/text_vertex.glsl", "./shader/text_fragment.glsl");
+    let background_shader_program = create_shader_program(
+        "./shader/screen_vertex.glsl",
+        "./shader/background_fragment.glsl",
+    );
+    let trail_shader_program =
+        create_shader_program("./shader/trail_vertex.glsl", "./shader/trail_fragment.glsl");
+    let lens_shader_program =
+        create_shader_program("./shader/screen_vertex.glsl", "./shader/lens_fragment.glsl");
+
+    let square = SquareObject::new(
+        Vec3::new(200.0, 300.0, 0.0),
+        std::f32::consts::PI / 6.,
+        100.0,
+        Color::new(128, 128, 51, 255),
+    );
 
-    let blackhole = BallObject::new(
+    let mut blackhole = BallObject::new(
         Vec3::new(400.0, 300.0, 0.0),
         Vec3::new(0., 0., 0.),
         100.,
@@ -122,6 +167,9 @@ fn window() {
         true,
     );
 
+    blackhole.is_black_hole = true;
+    blackhole.potential = Potential::PaczynskiWiita;
+
     let ball1 = BallObject::new(
         Vec3::new(200.0, 100.0, 0.0),
         Vec3::new(0., 40., 0.),
@@ -141,18 +189,67 @@ fn window() {
         false,
     );
 
-    let mut ball_objects: Vec<BallObject> = vec![ball1, blackhole];
-    // let mut square_objects: Vec<SquareObject> = vec![];
+    let (window_width, window_height) = window.get_size();
+    let mut world = World::new(window_width as f32, window_height as f32);
+    let mut camera = Camera::new(world.width, world.height);
+    // Screen position of the cursor while middle-dragging
+    let mut pan_from: Option<Vec2> = None;
+    world.add_ball(ball1);
+    world.add_ball(blackhole);
+    world.add_square(square);
+    if let Some(pentagon) = PolygonObject::regular(
+        Vec3::new(650.0, 450.0, 0.0),
+        Vec3::new(0., 0., 0.),
+        5,
+        40.0,
+        Color::new(200, 120, 60, 255),
+        50.0,
+        true,
+    ) {
+        world.add_polygon(pentagon);
+    }
+
+    // A scene file given on the command line replaces the built-in scene and is where F5 saves to
+    let scene_path = std::env::args().nth(1);
+    if let Some(path) = &scene_path {
+        let scene = Scene::load(path).unwrap_or_else(|e| panic!("Failed to load {path}: {e}"));
+        scene.apply(&mut world);
+    }
+    let scene_path = scene_path.unwrap_or_else(|| "scene.toml".to_string());
+
     let text_renderer = TextRenderer::new(text_shader_program);
+    let world_renderer = WorldRenderer::new(shader_program);
+    let line_renderer = LineRenderer::new();
+    let trail_renderer = TrailRenderer::new(trail_shader_program);
+    let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+    let mut lensing_pass = LensingPass::new(
+        background_shader_program,
+        lens_shader_program,
+        framebuffer_width,
+        framebuffer_height,
+    );
+    let mut lensing = false;
+    let mut spawn_mode = SpawnMode::Slingshot;
+    let mut eccentricity: f32 = 0.0;
+    let mut polygon_sides = 3;
+    // Where the current slingshot drag started
+    let mut aim_start: Option<Vec3> = None;
+    let mut wall_start: Option<Vec3> = None;
 
     let mut last_time = glfw.get_time() as f32;
     let mut frame_count = 0;
     let mut fps_timer = 0.0;
+    let mut diagnostics_timer = 0.0;
+    let mut start = world.diagnostics_baseline();
+    let mut current = world.diagnostics();
 
     let mut radius = 15.;
     let mut mass = 15.;
     let mut fps = 0.;
 
+    let mut snapshots: [Option<Snapshot>; 4] = [None, None, None, None];
+    let mut session = Session::Live;
+
     // Render loop
     while !window.should_close() {
         //FPS
@@ -165,60 +262,207 @@ fn window() {
             fps = frame_count as f32 / fps_timer;
         }
 
+        let alpha = session.advance(&mut world, delta_time);
+
+        if let Some(from) = pan_from {
+            let to = screen_cursor(&window);
+            camera.pan(to - from);
+            pan_from = Some(to);
+        }
+        camera.track(&world, alpha);
+        let cursor = cursor_position(&window, &camera);
         mouse_ball.radius = radius;
-        mouse_ball.position = Vec3::new(
-            window.get_cursor_pos().0 as f32,
-            SRC_HEIGHT as f32 - window.get_cursor_pos().1 as f32,
-            0.,
-        );
-
-        let len = ball_objects.len();
-
-        //Update physics for balls
-        for i in 0..len {
-            ball_objects[i].update(delta_time);
-            ball_objects[i].wall_collision();
-
-            for j in 0..len {
-                if i != j {
-                    let other = ball_objects[j].clone();
-                    if ball_objects[i].has_gravity {
-                        ball_objects[i].gravity_update(&other, delta_time);
-                    }
-                }
-            }
-            for j in (i + 1)..len {
-                let (left, right) = ball_objects.split_at_mut(j);
+        mouse_ball.position = aim_start.unwrap_or(cursor);
 
-                if left[i].has_collision && right[0].has_collision {
-                    left[i].check_ball_ball_collision(&mut right[0]);
-                }
-            }
+        diagnostics_timer += delta_time;
+        if diagnostics_timer >= DIAGNOSTICS_INTERVAL {
+            diagnostics_timer = 0.0;
+            start = world.diagnostics_baseline();
+            current = world.diagnostics();
         }
 
         unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+            let projection = camera.projection();
+            let screen =
+                Mat4::orthographic_rh_gl(0.0, camera.viewport.x, 0.0, camera.viewport.y, -1.0, 1.0);
+
+            if lensing {
+                lensing_pass.begin();
+            } else {
+                gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
 
-            let ortho =
-                Mat4::orthographic_rh_gl(0.0, SRC_WIDTH as f32, 0.0, SRC_HEIGHT as f32, -1.0, 1.0);
+            trail_renderer.render(&world, &projection);
+            if spawn_mode != SpawnMode::Wall {
+                world_renderer.render_ball(&mouse_ball, 1.0, &projection);
+            }
+            let pending_velocity = match spawn_mode {
+                SpawnMode::Slingshot => aim_start.map(|start| (start - cursor) * SLINGSHOT_SCALE),
+                SpawnMode::Orbit => world.orbital_velocity(cursor, eccentricity),
+                SpawnMode::Wall => None,
+            };
+            if let Some(velocity) = pending_velocity {
+                let mut ghost = mouse_ball.clone();
+                ghost.velocity = velocity;
+                ghost.has_collision = true;
+                let trajectory = world.predict_trajectory(&ghost, TRAJECTORY_PREVIEW_SECONDS);
+                line_renderer.draw_polyline(
+                    &trajectory,
+                    Vec3::new(0.8, 0.8, 0.8),
+                    shader_program,
+                    &projection,
+                );
+            }
+            if let Some(start) = aim_start {
+                let launch = (start - cursor) * SLINGSHOT_SCALE;
+                if launch.length() > 1.0 {
+                    line_renderer.draw_vector(
+                        start,
+                        launch,
+                        launch.length() / SLINGSHOT_SCALE,
+                        Vec3::new(1.0, 1.0, 1.0),
+                        shader_program,
+                        &projection,
+                    );
+                }
+            }
+            if let Some(start) = wall_start {
+                line_renderer.draw_line(
+                    start,
+                    cursor,
+                    Vec3::new(0.8, 0.8, 0.8),
+                    shader_program,
+                    &projection,
+                );
+            }
+            if world.boundary != Boundary::Open {
+                let corners = [
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Vec3::new(world.width, 0.0, 0.0),
+                    Vec3::new(world.width, world.height, 0.0),
+                    Vec3::new(0.0, world.height, 0.0),
+                    Vec3::new(0.0, 0.0, 0.0),
+                ];
+                line_renderer.draw_polyline(
+                    &corners,
+                    Vec3::new(0.5, 0.5, 0.5),
+                    shader_program,
+                    &projection,
+                );
+            }
+            world_renderer.render(&world, alpha, &projection);
 
-            text_renderer.draw(&format!("FPS {fps:.0}"), 10.0, 70.0, 24.0, &ortho);
-            text_renderer.draw(&format!("Radius {radius:.0}"), 10.0, 40.0, 24.0, &ortho);
-            text_renderer.draw(&format!("Mass {mass:.0}"), 10.0, 10.0, 24.0, &ortho);
+            if lensing {
+                lensing_pass.end(&lensing_pass.lenses(&world, alpha, &projection));
+            }
 
-            mouse_ball.render(shader_program, &ortho);
-            for ball in &mut ball_objects {
-                ball.render(shader_program, &ortho);
+            let solver = match world.gravity_solver {
+                GravitySolver::DirectSum => world.gravity_solver.name().to_string(),
+                GravitySolver::BarnesHut => {
+                    format!("{} {:.1}", world.gravity_solver.name(), world.theta)
+                }
+            };
+            let mut hud = vec![
+                format!(
+                    "Energy {:.4e} drift {:+.2e}",
+                    current.total_energy(),
+                    current.energy_drift(&start)
+                ),
+                format!(
+                    "Momentum {:.1} drift {:.2e}",
+                    current.momentum.length(),
+                    current.momentum_drift(&start)
+                ),
+                format!(
+                    "Angular momentum {:.4e} drift {:+.2e}",
+                    current.angular_momentum,
+                    current.angular_momentum_drift(&start)
+                ),
+                format!(
+                    "G {:.1} Softening {:.0}",
+                    world.gravity.g, world.gravity.softening
+                ),
+                format!("Collisions {}", world.collision_response.name()),
+                match world.boundary {
+                    Boundary::Reflect => {
+                        format!(
+                            "Boundary {} {:.2}",
+                            world.boundary.name(),
+                            world.restitution
+                        )
+                    }
+                    _ => format!("Boundary {}", world.boundary.name()),
+                },
+                solver,
+                format!("{} x{}", world.integrator.name(), world.substeps),
+                match spawn_mode {
+                    SpawnMode::Slingshot | SpawnMode::Wall => spawn_mode.name().to_string(),
+                    SpawnMode::Orbit => format!("{} e {eccentricity:.1}", spawn_mode.name()),
+                },
+                format!("FPS {fps:.0}"),
+                format!("Zoom {:.2} {}", camera.zoom, camera.follow.name()),
+                format!("Radius {radius:.0}"),
+                format!("Mass {mass:.0}"),
+            ];
+            if world.event_horizon {
+                hud.insert(3, format!("Swallowed {:.0}", world.swallowed_mass));
+            }
+            if let Some(hole) = world.balls.iter().find(|b| b.is_black_hole) {
+                hud.insert(3, format!("Black hole {}", hole.potential.name()));
+            }
+            if !matches!(session, Session::Live) {
+                hud.insert(0, session.name());
+            }
+            for (i, line) in hud.iter().rev().enumerate() {
+                text_renderer.draw(line, 10.0, 10.0 + 30.0 * i as f32, 24.0, &screen);
             }
         }
 
         glfw.poll_events();
         for (_, event) in glfw::flush_messages(&events) {
             match event {
+                // World units are screen coordinates, so on HiDPI screens the framebuffer has
+                // more pixels than the world has units
+                glfw::WindowEvent::Size(width, height) if width > 0 && height > 0 => {
+                    session.execute(
+                        &mut world,
+                        Command::Resize {
+                            width: width as f32,
+                            height: height as f32,
+                        },
+                    );
+                    camera.resize(Vec2::new(width as f32, height as f32));
+                }
+                glfw::WindowEvent::FramebufferSize(width, height) if width > 0 && height > 0 => {
+                    unsafe {
+                        gl::Viewport(0, 0, width, height);
+                    }
+                    lensing_pass.resize(width, height);
+                }
                 glfw::WindowEvent::Scroll(_, y) if y > 0.0 => {
                     let shift = window.get_key(Key::LeftShift) == Action::Press;
-                    if shift {
+                    if window.get_key(Key::LeftControl) == Action::Press {
+                        camera.zoom_at(screen_cursor(&window), 1.1);
+                    } else if window.get_key(Key::G) == Action::Press {
+                        let command = Command::SetGravity {
+                            g: world.gravity.g * 1.1,
+                            softening: world.gravity.softening,
+                        };
+                        session.execute(&mut world, command);
+                    } else if window.get_key(Key::E) == Action::Press {
+                        let command = Command::SetGravity {
+                            g: world.gravity.g,
+                            softening: world.gravity.softening + 1.,
+                        };
+                        session.execute(&mut world, command);
+                    } else if window.get_key(Key::R) == Action::Press {
+                        let command = Command::SetBoundary {
+                            boundary: world.boundary,
+                            restitution: (world.restitution + 0.05).min(1.0),
+                        };
+                        session.execute(&mut world, command);
+                    } else if shift {
                         mass += 1.;
                     } else {
                         radius += 1.;
@@ -226,7 +470,27 @@ fn window() {
                 }
                 glfw::WindowEvent::Scroll(_, y) if y < 0.0 => {
                     let shift = window.get_key(Key::LeftShift) == Action::Press;
-                    if shift {
+                    if window.get_key(Key::LeftControl) == Action::Press {
+                        camera.zoom_at(screen_cursor(&window), 1.0 / 1.1);
+                    } else if window.get_key(Key::G) == Action::Press {
+                        let command = Command::SetGravity {
+                            g: world.gravity.g / 1.1,
+                            softening: world.gravity.softening,
+                        };
+                        session.execute(&mut world, command);
+                    } else if window.get_key(Key::E) == Action::Press {
+                        let command = Command::SetGravity {
+                            g: world.gravity.g,
+                            softening: (world.gravity.softening - 1.).max(0.),
+                        };
+                        session.execute(&mut world, command);
+                    } else if window.get_key(Key::R) == Action::Press {
+                        let command = Command::SetBoundary {
+                            boundary: world.boundary,
+                            restitution: (world.restitution - 0.05).max(0.0),
+                        };
+                        session.execute(&mut world, command);
+                    } else if shift {
                         mass -= 1.;
                     } else {
                         radius -= 1.;
@@ -234,10 +498,261 @@ fn window() {
                 }
 
                 glfw::WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
-                    spawn_ball(&mut ball_objects, &mut window, 1, radius, mass);
+                    let position = cursor_position(&window, &camera);
+                    match spawn_mode {
+                        SpawnMode::Slingshot => aim_start = Some(position),
+                        SpawnMode::Orbit => {
+                            let velocity = world
+                                .orbital_velocity(position, eccentricity)
+                                .unwrap_or(Vec3::ZERO);
+                            let command = Command::SpawnBall {
+                                position,
+                                velocity,
+                                radius,
+                                mass,
+                            };
+                            session.execute(&mut world, command);
+                        }
+                        SpawnMode::Wall => wall_start = Some(position),
+                    }
+                }
+                glfw::WindowEvent::MouseButton(MouseButton::Button3, Action::Press, _) => {
+                    camera.follow = Follow::Free;
+                    pan_from = Some(screen_cursor(&window));
+                }
+                glfw::WindowEvent::MouseButton(MouseButton::Button3, Action::Release, _) => {
+                    pan_from = None;
+                }
+                glfw::WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => {
+                    let command = Command::AddSquare {
+                        position: cursor_position(&window, &camera),
+                        size: radius * 2.0,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => {
+                    if let Some(start) = aim_start.take() {
+                        let velocity =
+                            (start - cursor_position(&window, &camera)) * SLINGSHOT_SCALE;
+                        let command = Command::SpawnBall {
+                            position: start,
+                            velocity,
+                            radius,
+                            mass,
+                        };
+                        session.execute(&mut world, command);
+                    }
+                    if let Some(start) = wall_start.take() {
+                        let end = cursor_position(&window, &camera);
+                        if (end - start).length() > 1.0 {
+                            session.execute(&mut world, Command::AddSegment { start, end });
+                        }
+                    }
+                }
+                glfw::WindowEvent::Key(Key::O, _, Action::Press, _) => {
+                    spawn_mode = spawn_mode.next();
+                    aim_start = None;
+                    wall_start = None;
+                }
+                glfw::WindowEvent::Key(Key::Period, _, Action::Press, _) => {
+                    eccentricity = (eccentricity + 0.1).min(0.9);
+                }
+                glfw::WindowEvent::Key(Key::Comma, _, Action::Press, _) => {
+                    eccentricity = (eccentricity - 0.1).max(0.0);
+                }
+                glfw::WindowEvent::Key(Key::N, _, Action::Press, _) => {
+                    let command = Command::AddPolygon {
+                        position: cursor_position(&window, &camera),
+                        sides: polygon_sides,
+                        radius: radius * 2.0,
+                        mass,
+                    };
+                    session.execute(&mut world, command);
+                    polygon_sides = if polygon_sides >= 6 {
+                        3
+                    } else {
+                        polygon_sides + 1
+                    };
+                }
+                // Follow the ball under the cursor, otherwise toggle between the barycentre and free
+                glfw::WindowEvent::Key(Key::F, _, Action::Press, _) => {
+                    camera.follow = match world.ball_at(cursor_position(&window, &camera)) {
+                        Some(i) => Follow::Body(world.balls[i].id),
+                        None if camera.follow == Follow::Free => Follow::Barycentre,
+                        None => Follow::Free,
+                    };
+                }
+                glfw::WindowEvent::Key(Key::T, _, Action::Press, _) => {
+                    let command = Command::SetTrails {
+                        trails: !world.trails,
+                        trail_length: world.trail_length,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::Equal, _, Action::Press, _) => {
+                    let command = Command::SetTrails {
+                        trails: world.trails,
+                        trail_length: world.trail_length.saturating_mul(2),
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::Minus, _, Action::Press, _) => {
+                    let command = Command::SetTrails {
+                        trails: world.trails,
+                        trail_length: (world.trail_length / 2).max(2),
+                    };
+                    session.execute(&mut world, command);
                 }
                 glfw::WindowEvent::Key(Key::C, _, Action::Press, _) => {
-                    ball_objects.clear();
+                    session.execute(&mut world, Command::Clear);
+                    camera.follow = Follow::Free;
+                }
+                glfw::WindowEvent::Key(Key::F5, _, Action::Press, _) => {
+                    if let Err(e) = Scene::capture(&world).save(&scene_path) {
+                        eprintln!("Failed to save {scene_path}: {e}");
+                    }
+                }
+                glfw::WindowEvent::Key(Key::F9, _, Action::Press, _) => {
+                    match Scene::load(&scene_path) {
+                        Ok(scene) => {
+                            finish_recording(&mut session);
+                            scene.apply(&mut world);
+                            camera.follow = Follow::Free;
+                            aim_start = None;
+                            wall_start = None;
+                        }
+                        Err(e) => eprintln!("Failed to load {scene_path}: {e}"),
+                    }
+                }
+                glfw::WindowEvent::Key(
+                    key @ (Key::F1 | Key::F2 | Key::F3 | Key::F4),
+                    _,
+                    Action::Press,
+                    mods,
+                ) => {
+                    let slot = match key {
+                        Key::F1 => 0,
+                        Key::F2 => 1,
+                        Key::F3 => 2,
+                        _ => 3,
+                    };
+                    if !mods.contains(glfw::Modifiers::Shift) {
+                        snapshots[slot] = Some(Snapshot {
+                            world: world.clone(),
+                            spawn_mode,
+                            eccentricity,
+                            radius,
+                            mass,
+                        });
+                    } else if let Some(snapshot) = &snapshots[slot] {
+                        finish_recording(&mut session);
+                        // The window may have been resized since, the world keeps its current size
+                        let (width, height) = (world.width, world.height);
+                        world = snapshot.world.clone();
+                        world.width = width;
+                        world.height = height;
+                        spawn_mode = snapshot.spawn_mode;
+                        eccentricity = snapshot.eccentricity;
+                        radius = snapshot.radius;
+                        mass = snapshot.mass;
+                        aim_start = None;
+                        wall_start = None;
+                    }
+                }
+                // Starts a recording from the current state, or ends and saves the running one
+                glfw::WindowEvent::Key(Key::F6, _, Action::Press, _) => {
+                    if matches!(session, Session::Recording(_)) {
+                        finish_recording(&mut session);
+                    } else {
+                        match Recording::start(&mut world) {
+                            Ok(recording) => session = Session::Recording(recording),
+                            Err(e) => eprintln!("Failed to start recording: {e}"),
+                        }
+                    }
+                }
+                glfw::WindowEvent::Key(Key::F10, _, Action::Press, _) => {
+                    finish_recording(&mut session);
+                    match Recording::load(RECORDING_PATH) {
+                        Ok(recording) => {
+                            session = Session::Replay(Replay::new(recording, &mut world));
+                            camera.follow = Follow::Free;
+                            aim_start = None;
+                            wall_start = None;
+                        }
+                        Err(e) => eprintln!("Failed to load {RECORDING_PATH}: {e}"),
+                    }
+                }
+                glfw::WindowEvent::Key(Key::I, _, Action::Press, _) => {
+                    let command = Command::SetIntegrator {
+                        integrator: world.integrator.next(),
+                        substeps: world.substeps,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::M, _, Action::Press, _) => {
+                    let command = Command::SetCollisionResponse {
+                        collision_response: world.collision_response.next(),
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::W, _, Action::Press, _) => {
+                    let command = Command::SetBoundary {
+                        boundary: world.boundary.next(),
+                        restitution: world.restitution,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::P, _, Action::Press, _) => {
+                    if let Some(hole) = world.balls.iter().find(|b| b.is_black_hole) {
+                        let command = Command::SetBlackHolePotential {
+                            potential: hole.potential.next(),
+                        };
+                        session.execute(&mut world, command);
+                    }
+                }
+                glfw::WindowEvent::Key(Key::L, _, Action::Press, _) => {
+                    lensing = !lensing;
+                }
+                glfw::WindowEvent::Key(Key::H, _, Action::Press, _) => {
+                    let command = Command::SetEventHorizon {
+                        event_horizon: !world.event_horizon,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::B, _, Action::Press, _) => {
+                    let command = Command::SetGravitySolver {
+                        gravity_solver: world.gravity_solver.next(),
+                        theta: world.theta,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::RightBracket, _, Action::Press, _) => {
+                    let command = Command::SetGravitySolver {
+                        gravity_solver: world.gravity_solver,
+                        theta: world.theta + 0.1,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::LeftBracket, _, Action::Press, _) => {
+                    let command = Command::SetGravitySolver {
+                        gravity_solver: world.gravity_solver,
+                        theta: (world.theta - 0.1).max(0.0),
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::Up, _, Action::Press, _) => {
+                    let command = Command::SetIntegrator {
+                        integrator: world.integrator,
+                        substeps: world.substeps + 1,
+                    };
+                    session.execute(&mut world, command);
+                }
+                glfw::WindowEvent::Key(Key::Down, _, Action::Press, _) => {
+                    let command = Command::SetIntegrator {
+                        integrator: world.integrator,
+                        substeps: world.substeps.saturating_sub(1).max(1),
+                    };
+                    session.execute(&mut world, command);
                 }
 
                 _ => {}
@@ -249,34 +764,25 @@ fn window() {
     }
 }
 
-fn spawn_ball(
-    array: &mut Vec<BallObject>,
-    window: &mut glfw::Window,
-    count: i32,
-    radius: f32,
-    mass: f32,
-) {
-    for _i in 0..count {
-        let (xpos, ypos) = window.get_cursor_pos();
-        let flipped_ypos = SRC_HEIGHT as f64 - ypos;
-
-        let ball = BallObject::new(
-            Vec3::new(xpos as f32, flipped_ypos as f32, 0.0),
-            Vec3::new(50.0, 0.0, 0.),
-            radius,
-            Color::new(
-                rand::random_range(0..=255),
-                rand::random_range(0..=255),
-                rand::random_range(0..=255),
-                255,
-            ),
-            mass,
-            true,
-            true,
-        );
-        array.push(ball);
+fn screen_cursor(window: &glfw::Window) -> Vec2 {
+    let (xpos, ypos) = window.get_cursor_pos();
+    Vec2::new(xpos as f32, ypos as f32)
+}
+
+fn cursor_position(window: &glfw::Window, camera: &Camera) -> Vec3 {
+    camera.screen_to_world(screen_cursor(window))
+}
+
+/// Ends a recording or replay. A recording is saved, since it can't be continued once the
+/// world is replaced by something it didn't record.
+fn finish_recording(session: &mut Session) {
+    if let Some(recording) = session.finish()
+        && let Err(e) = recording.save(RECORDING_PATH)
+    {
+        eprintln!("Failed to save {RECORDING_PATH}: {e}");
     }
 }
+
 fn main() {
     window();
 }
@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    ball_obj::Color,
+    collision::{ContactBody, wrap_position},
+};
+
+/// Twice the area below which a polygon is too thin to have a centroid or inertia
+const MIN_AREA: f32 = 1e-3;
+
+/// Convex rigid body that can move and spin.
+#[derive(Clone)]
+pub struct PolygonObject {
+    /// Assigned by `World::add_polygon` from the same counter as ball ids
+    pub id: u64,
+    pub position: Vec3,
+    pub previous_position: Vec3,
+    pub velocity: Vec3,
+    pub rotation: f32,
+    pub previous_rotation: f32,
+    pub angular_velocity: f32,
+    /// Counter-clockwise around the centroid
+    pub vertices: Vec<Vec2>,
+    pub color: Color,
+    pub mass: f32,
+    pub inertia: f32,
+    pub has_gravity: bool,
+    /// Recent positions of the centroid, oldest first
+    pub trail: VecDeque<Vec3>,
+}
+
+impl PolygonObject {
+    /// `vertices` are local to `position` and get re-centered on their centroid and
+    /// wound counter-clockwise. None if they don't enclose any area.
+    pub fn new(
+        position: Vec3,
+        velocity: Vec3,
+        mut vertices: Vec<Vec2>,
+        color: Color,
+        mass: f32,
+        has_gravity: bool,
+    ) -> Option<Self> {
+        let mut area = 0.0;
+        let mut centroid = Vec2::ZERO;
+        for (i, &p1) in vertices.iter().enumerate() {
+            let p2 = vertices[(i + 1) % vertices.len()];
+            let cross = p1.perp_dot(p2);
+            area += cross;
+            centroid += (p1 + p2) * cross;
+        }
+        if area.abs() < MIN_AREA {
+            return None;
+        }
+        centroid /= 3.0 * area;
+        if area < 0.0 {
+            vertices.reverse();
+            area = -area;
+        }
+
+        let vertices: Vec<Vec2> = vertices.iter().map(|v| *v - centroid).collect();
+
+        //I = m / 6 * sum(cross * (p1.p1 + p1.p2 + p2.p2)) / sum(cross)
+        let mut numerator = 0.0;
+        for (i, &p1) in vertices.iter().enumerate() {
+            let p2 = vertices[(i + 1) % vertices.len()];
+            numerator += p1.perp_dot(p2) * (p1.dot(p1) + p1.dot(p2) + p2.dot(p2));
+        }
+        let inertia = mass / 6.0 * numerator / area;
+
+        let position = position + centroid.extend(0.0);
+
+        Some(PolygonObject {
+            id: 0,
+            position,
+            previous_position: position,
+            velocity,
+            rotation: 0.0,
+            previous_rotation: 0.0,
+            angular_velocity: 0.0,
+            vertices,
+            color,
+            mass,
+            inertia,
+            has_gravity,
+            trail: VecDeque::new(),
+        })
+    }
+
+    pub fn regular(
+        position: Vec3,
+        velocity: Vec3,
+        sides: usize,
+        radius: f32,
+        color: Color,
+        mass: f32,
+        has_gravity: bool,
+    ) -> Option<Self> {
+        let vertices = (0..sides)
+            .map(|i| {
+                let angle = (i as f32 / sides as f32) * 2.0 * std::f32::consts::PI;
+                Vec2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+
+        PolygonObject::new(position, velocity, vertices, color, mass, has_gravity)
+    }
+
+    pub fn bounding_radius(&self) -> f32 {
+        self.vertices.iter().map(|v| v.length()).fold(0.0, f32::max)
+    }
+
+    pub fn wrap(&mut self, width: f32, height: f32) {
+        let wrapped = wrap_position(self.position, width, height);
+        if wrapped != self.position {
+            self.previous_position += wrapped - self.position;
+            self.position = wrapped;
+            self.trail.clear();
+        }
+    }
+
+    pub fn get_vertices_at(&self, position: Vec3, rotation: f32) -> Vec<Vec2> {
+        let cos = rotation.cos();
+        let sin = rotation.sin();
+
+        self.vertices
+            .iter()
+            .map(|v| Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos) + position.truncate())
+            .collect()
+    }
+
+    pub fn get_vertices(&self) -> Vec<Vec2> {
+        self.get_vertices_at(self.position, self.rotation)
+    }
+
+    pub fn get_normals(&self) -> Vec<Vec2> {
+        let cos = self.rotation.cos();
+        let sin = self.rotation.sin();
+
+        let mut normals = Vec::new();
+
+        for i in 0..self.vertices.len() {
+            let p1 = self.vertices[i];
+
+            let p2 = self.vertices[(i + 1) % self.vertices.len()];
+
+            let edge = p2 - p1;
+
+            let local_normal = Vec2::new(edge.y, -edge.x).normalize();
+
+            let world_normal = Vec2::new(
+                local_normal.x * cos - local_normal.y * sin,
+                local_normal.x * sin + local_normal.y * cos,
+            );
+
+            normals.push(world_normal);
+        }
+
+        normals
+    }
+
+    pub fn contact_body(&self) -> ContactBody {
+        ContactBody {
+            center: self.position.truncate(),
+            velocity: self.velocity.truncate(),
+            angular_velocity: self.angular_velocity,
+            inverse_mass: if self.mass > 0.0 {
+                1.0 / self.mass
+            } else {
+                0.0
+            },
+            inverse_inertia: if self.inertia > 0.0 {
+                1.0 / self.inertia
+            } else {
+                0.0
+            },
+        }
+    }
+
+    pub fn apply_contact_body(&mut self, body: &ContactBody) {
+        self.position = body.center.extend(self.position.z);
+        self.velocity = body.velocity.extend(self.velocity.z);
+        self.angular_velocity = body.angular_velocity;
+    }
+}
@@ -0,0 +1,149 @@
+use glam::{Vec2, Vec3};
+
+use crate::gravity::Gravity;
+
+// Bodies sharing a cell past this depth are lumped together instead of subdividing forever
+const MAX_DEPTH: u32 = 32;
+
+struct Node {
+    center: Vec2,
+    half_size: f32,
+    mass: f32,
+    center_of_mass: Vec3,
+    body: Option<usize>,
+    children: Option<[usize; 4]>,
+}
+
+impl Node {
+    fn new(center: Vec2, half_size: f32) -> Self {
+        Node {
+            center,
+            half_size,
+            mass: 0.0,
+            center_of_mass: Vec3::ZERO,
+            body: None,
+            children: None,
+        }
+    }
+
+    fn quadrant(&self, position: Vec3) -> usize {
+        let right = position.x >= self.center.x;
+        let top = position.y >= self.center.y;
+        match (right, top) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        }
+    }
+}
+
+/// Barnes-Hut tree over the bodies of one step.
+pub struct QuadTree {
+    nodes: Vec<Node>,
+}
+
+impl QuadTree {
+    pub fn build(positions: &[Vec3], masses: &[f32]) -> Self {
+        let mut min = Vec2::splat(f32::MAX);
+        let mut max = Vec2::splat(f32::MIN);
+        for position in positions {
+            min = min.min(position.truncate());
+            max = max.max(position.truncate());
+        }
+        let center = (min + max) * 0.5;
+        let half_size = ((max - min).max_element() * 0.5).max(1.0);
+
+        let mut tree = QuadTree {
+            nodes: vec![Node::new(center, half_size)],
+        };
+
+        for i in 0..positions.len() {
+            if masses[i] > 0.0 {
+                tree.insert(0, i, positions, masses, 0);
+            }
+        }
+
+        tree
+    }
+
+    fn insert(&mut self, node: usize, body: usize, positions: &[Vec3], masses: &[f32], depth: u32) {
+        let position = positions[body];
+        let mass = masses[body];
+
+        let is_empty = self.nodes[node].mass == 0.0 && self.nodes[node].children.is_none();
+        if is_empty {
+            let n = &mut self.nodes[node];
+            n.body = Some(body);
+            n.mass = mass;
+            n.center_of_mass = position;
+            return;
+        }
+
+        if self.nodes[node].children.is_none() && depth < MAX_DEPTH {
+            self.subdivide(node);
+            if let Some(existing) = self.nodes[node].body.take() {
+                let child = self.child_for(node, positions[existing]);
+                self.insert(child, existing, positions, masses, depth + 1);
+            }
+        }
+
+        let n = &mut self.nodes[node];
+        let total = n.mass + mass;
+        n.center_of_mass = (n.center_of_mass * n.mass + position * mass) / total;
+        n.mass = total;
+
+        if self.nodes[node].children.is_some() {
+            let child = self.child_for(node, position);
+            self.insert(child, body, positions, masses, depth + 1);
+        } else {
+            self.nodes[node].body = None;
+        }
+    }
+
+    fn subdivide(&mut self, node: usize) {
+        let center = self.nodes[node].center;
+        let quarter = self.nodes[node].half_size * 0.5;
+        let first = self.nodes.len();
+        for offset in [
+            Vec2::new(-quarter, -quarter),
+            Vec2::new(quarter, -quarter),
+            Vec2::new(-quarter, quarter),
+            Vec2::new(quarter, quarter),
+        ] {
+            self.nodes.push(Node::new(center + offset, quarter));
+        }
+        self.nodes[node].children = Some([first, first + 1, first + 2, first + 3]);
+    }
+
+    fn child_for(&self, node: usize, position: Vec3) -> usize {
+        let n = &self.nodes[node];
+        n.children.unwrap()[n.quadrant(position)]
+    }
+
+    /// Acceleration on `body` at `position`. Cells whose size over distance is below `theta`
+    /// are treated as a single point mass.
+    pub fn acceleration(&self, body: usize, position: Vec3, theta: f32, gravity: &Gravity) -> Vec3 {
+        let mut acceleration = Vec3::ZERO;
+        let mut stack = vec![0];
+
+        while let Some(node) = stack.pop() {
+            let n = &self.nodes[node];
+            if n.mass == 0.0 || n.body == Some(body) {
+                continue;
+            }
+
+            let direction = n.center_of_mass - position;
+            let distance = direction.length();
+
+            match n.children {
+                Some(children) if n.half_size * 2.0 >= theta * distance => {
+                    stack.extend_from_slice(&children);
+                }
+                _ => acceleration += gravity.point_acceleration(direction, n.mass),
+            }
+        }
+
+        acceleration
+    }
+}
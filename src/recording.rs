@@ -0,0 +1,390 @@
+use std::{error::Error, fs};
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball_obj::{BallObject, Color},
+    collision::{Boundary, CollisionResponse},
+    gravity::{GravitySolver, Potential},
+    integrator::Integrator,
+    polygon_obj::PolygonObject,
+    scene::Scene,
+    segment_obj::SegmentObject,
+    square_obj::SquareObject,
+    world::{MAX_TRAIL_LENGTH, World},
+};
+
+/// A change the user makes to the world. Values are absolute so a replay doesn't depend on
+/// what a key would have cycled from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Command {
+    SpawnBall {
+        position: Vec3,
+        velocity: Vec3,
+        radius: f32,
+        mass: f32,
+    },
+    AddSquare {
+        position: Vec3,
+        size: f32,
+    },
+    AddPolygon {
+        position: Vec3,
+        sides: usize,
+        radius: f32,
+        mass: f32,
+    },
+    AddSegment {
+        start: Vec3,
+        end: Vec3,
+    },
+    Clear,
+    Resize {
+        width: f32,
+        height: f32,
+    },
+    SetGravity {
+        g: f32,
+        softening: f32,
+    },
+    SetGravitySolver {
+        gravity_solver: GravitySolver,
+        theta: f32,
+    },
+    SetIntegrator {
+        integrator: Integrator,
+        substeps: u32,
+    },
+    SetCollisionResponse {
+        collision_response: CollisionResponse,
+    },
+    SetBoundary {
+        boundary: Boundary,
+        restitution: f32,
+    },
+    SetBlackHolePotential {
+        potential: Potential,
+    },
+    SetEventHorizon {
+        event_horizon: bool,
+    },
+    SetTrails {
+        trails: bool,
+        trail_length: usize,
+    },
+}
+
+impl Command {
+    pub fn apply(&self, world: &mut World) {
+        match *self {
+            Command::SpawnBall {
+                position,
+                velocity,
+                radius,
+                mass,
+            } => {
+                let color = world.random_color();
+                world.add_ball(BallObject::new(
+                    position, velocity, radius, color, mass, true, true,
+                ));
+            }
+            Command::AddSquare { position, size } => {
+                world.add_square(SquareObject::new(
+                    position,
+                    0.0,
+                    size,
+                    Color::new(128, 128, 51, 255),
+                ));
+            }
+            Command::AddPolygon {
+                position,
+                sides,
+                radius,
+                mass,
+            } => {
+                let color = world.random_color();
+                let polygon =
+                    PolygonObject::regular(position, Vec3::ZERO, sides, radius, color, mass, true);
+                if let Some(polygon) = polygon {
+                    world.add_polygon(polygon);
+                }
+            }
+            Command::AddSegment { start, end } => {
+                world.add_segment(SegmentObject::new(
+                    start,
+                    end,
+                    Color::new(230, 230, 230, 255),
+                ));
+            }
+            Command::Clear => world.clear(),
+            Command::Resize { width, height } => {
+                world.width = width;
+                world.height = height;
+            }
+            Command::SetGravity { g, softening } => {
+                world.gravity.g = g;
+                world.gravity.softening = softening;
+                world.resize_black_holes();
+                world.reset_diagnostics();
+            }
+            Command::SetGravitySolver {
+                gravity_solver,
+                theta,
+            } => {
+                world.gravity_solver = gravity_solver;
+                world.theta = theta;
+            }
+            Command::SetIntegrator {
+                integrator,
+                substeps,
+            } => {
+                world.integrator = integrator;
+                world.substeps = substeps.max(1);
+            }
+            Command::SetCollisionResponse { collision_response } => {
+                world.collision_response = collision_response;
+            }
+            Command::SetBoundary {
+                boundary,
+                restitution,
+            } => {
+                world.boundary = boundary;
+                world.restitution = restitution;
+            }
+            Command::SetBlackHolePotential { potential } => {
+                for ball in world.balls.iter_mut().filter(|b| b.is_black_hole) {
+                    ball.potential = potential;
+                }
+                world.reset_diagnostics();
+            }
+            Command::SetEventHorizon { event_horizon } => {
+                world.event_horizon = event_horizon;
+            }
+            Command::SetTrails {
+                trails,
+                trail_length,
+            } => {
+                world.trails = trails;
+                world.trail_length = trail_length.min(MAX_TRAIL_LENGTH);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecordedCommand {
+    /// `World::tick` the command was given at, before the step that follows it
+    pub tick: u64,
+    pub command: Command,
+}
+
+/// Everything needed to reproduce a session: the starting state and the commands given.
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub width: f32,
+    pub height: f32,
+    pub scene: Scene,
+    #[serde(default)]
+    pub commands: Vec<RecordedCommand>,
+}
+
+impl Recording {
+    /// Starts recording from the current state of `world`, which is restarted from it.
+    pub fn start(world: &mut World) -> Result<Self, Box<dyn Error>> {
+        // Round-trip through TOML so the live run starts from exactly what the file will hold
+        let scene = toml::from_str(&toml::to_string(&Scene::capture(world))?)?;
+        let recording = Recording {
+            seed: rand::random(),
+            width: world.width,
+            height: world.height,
+            scene,
+            commands: Vec::new(),
+        };
+        recording.restart(world);
+        Ok(recording)
+    }
+
+    fn restart(&self, world: &mut World) {
+        world.width = self.width;
+        world.height = self.height;
+        self.scene.apply(world);
+        world.restart(self.seed);
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let recording: Recording = toml::from_str(&fs::read_to_string(path)?)?;
+        recording.scene.validate()?;
+        Ok(recording)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+pub struct Replay {
+    recording: Recording,
+    next: usize,
+}
+
+impl Replay {
+    /// Restarts `world` from the beginning of `recording`.
+    pub fn new(recording: Recording, world: &mut World) -> Self {
+        recording.restart(world);
+        Replay { recording, next: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next >= self.recording.commands.len()
+    }
+}
+
+/// Who is in control of the world. Only live and recording sessions take user commands.
+pub enum Session {
+    Live,
+    Recording(Recording),
+    Replay(Replay),
+}
+
+impl Session {
+    pub fn execute(&mut self, world: &mut World, command: Command) {
+        match self {
+            Session::Live => command.apply(world),
+            Session::Recording(recording) => {
+                command.apply(world);
+                recording.commands.push(RecordedCommand {
+                    tick: world.tick,
+                    command,
+                });
+            }
+            // User input would make the replay diverge from the recorded session
+            Session::Replay(_) => {}
+        }
+    }
+
+    /// `World::advance` that also feeds a replay its commands at their ticks.
+    pub fn advance(&mut self, world: &mut World, frame_time: f32) -> f32 {
+        let Session::Replay(replay) = self else {
+            return world.advance(frame_time);
+        };
+
+        let commands = &replay.recording.commands;
+        let next = &mut replay.next;
+        let alpha = world.advance_with(frame_time, |world| {
+            while *next < commands.len() && commands[*next].tick <= world.tick {
+                commands[*next].command.apply(world);
+                *next += 1;
+            }
+        });
+
+        if replay.finished() {
+            *self = Session::Live;
+        }
+        alpha
+    }
+
+    /// Ends a recording or replay, handing back the recording if one was being made.
+    pub fn finish(&mut self) -> Option<Recording> {
+        match std::mem::replace(self, Session::Live) {
+            Session::Recording(recording) => Some(recording),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Session::Live => "Live".to_string(),
+            Session::Recording(recording) => format!("Recording {}", recording.commands.len()),
+            Session::Replay(replay) => {
+                format!("Replay {}/{}", replay.next, replay.recording.commands.len())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_TIME: f32 = 1.0 / 60.0;
+
+    fn run(session: &mut Session, world: &mut World, frames: usize) {
+        for frame in 0..frames {
+            let command = match frame {
+                30 => Some(Command::SpawnBall {
+                    position: Vec3::new(300.0, 200.0, 0.0),
+                    velocity: Vec3::new(40.0, 10.0, 0.0),
+                    radius: 8.0,
+                    mass: 20.0,
+                }),
+                90 => Some(Command::SetGravity {
+                    g: 250.0,
+                    softening: 3.0,
+                }),
+                150 => Some(Command::AddPolygon {
+                    position: Vec3::new(600.0, 400.0, 0.0),
+                    sides: 5,
+                    radius: 30.0,
+                    mass: 40.0,
+                }),
+                200 => Some(Command::SetIntegrator {
+                    integrator: Integrator::Rk4,
+                    substeps: 3,
+                }),
+                _ => None,
+            };
+            if let Some(command) = command {
+                session.execute(world, command);
+            }
+            session.advance(world, FRAME_TIME);
+        }
+    }
+
+    #[test]
+    fn replay_reproduces_the_recorded_world() {
+        let mut world = World::new(1000.0, 800.0);
+        for i in 0..8 {
+            let color = world.random_color();
+            world.add_ball(BallObject::new(
+                Vec3::new(100.0 + i as f32 * 100.0, 400.0, 0.0),
+                Vec3::new(0.0, 20.0 - i as f32 * 5.0, 0.0),
+                10.0,
+                color,
+                10.0 + i as f32,
+                true,
+                true,
+            ));
+        }
+
+        let mut session = Session::Recording(Recording::start(&mut world).unwrap());
+        run(&mut session, &mut world, 300);
+        let recording = session.finish().unwrap();
+
+        let mut replayed = World::new(640.0, 480.0);
+        let mut session = Session::Replay(Replay::new(recording, &mut replayed));
+        // Replays ignore input, so the same frames only feed the recorded commands
+        run(&mut session, &mut replayed, 300);
+
+        assert_eq!(replayed.tick, world.tick);
+        assert_eq!(replayed.balls.len(), world.balls.len());
+        for (a, b) in replayed.balls.iter().zip(&world.balls) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.velocity, b.velocity);
+            assert_eq!(a.mass, b.mass);
+            assert_eq!(
+                (a.color.r, a.color.g, a.color.b),
+                (b.color.r, b.color.g, b.color.b)
+            );
+        }
+        assert_eq!(replayed.polygons.len(), world.polygons.len());
+        for (a, b) in replayed.polygons.iter().zip(&world.polygons) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.rotation, b.rotation);
+        }
+    }
+}
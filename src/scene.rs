@@ -0,0 +1,372 @@
+use std::{error::Error, fs};
+
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball_obj::{BallObject, Color},
+    collision::{Boundary, CollisionResponse},
+    gravity::{Gravity, GravitySolver, Potential},
+    integrator::Integrator,
+    polygon_obj::PolygonObject,
+    segment_obj::SegmentObject,
+    square_obj::SquareObject,
+    world::{MAX_TRAIL_LENGTH, MIN_FIXED_DELTA, World},
+};
+
+/// Hand-editable TOML description of a `World`. Everything but the bodies themselves
+/// may be left out and falls back to the defaults of `World::new`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub balls: Vec<BallDescription>,
+    #[serde(default)]
+    pub squares: Vec<SquareObject>,
+    #[serde(default)]
+    pub polygons: Vec<PolygonDescription>,
+    #[serde(default)]
+    pub segments: Vec<SegmentObject>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub g: f32,
+    pub softening: f32,
+    pub gravity_solver: GravitySolver,
+    pub theta: f32,
+    pub integrator: Integrator,
+    pub fixed_delta: f32,
+    pub substeps: u32,
+    pub collision_response: CollisionResponse,
+    pub boundary: Boundary,
+    pub restitution: f32,
+    pub event_horizon: bool,
+    pub trails: bool,
+    pub trail_length: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BallDescription {
+    pub position: Vec3,
+    #[serde(default)]
+    pub velocity: Vec3,
+    /// Replaced by the Schwarzschild radius for black holes
+    pub radius: f32,
+    pub color: Color,
+    pub mass: f32,
+    #[serde(default = "enabled")]
+    pub has_collision: bool,
+    #[serde(default = "enabled")]
+    pub has_gravity: bool,
+    #[serde(default)]
+    pub is_black_hole: bool,
+    #[serde(default = "newtonian")]
+    pub potential: Potential,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolygonDescription {
+    pub position: Vec3,
+    #[serde(default)]
+    pub velocity: Vec3,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub angular_velocity: f32,
+    /// Relative to `position`, in either winding order
+    pub vertices: Vec<Vec2>,
+    pub color: Color,
+    pub mass: f32,
+    #[serde(default = "enabled")]
+    pub has_gravity: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn newtonian() -> Potential {
+    Potential::Newtonian
+}
+
+impl Default for Settings {
+    // Same as `World::new`
+    fn default() -> Self {
+        let gravity = Gravity::default();
+        Settings {
+            g: gravity.g,
+            softening: gravity.softening,
+            gravity_solver: GravitySolver::DirectSum,
+            theta: 0.5,
+            integrator: Integrator::VelocityVerlet,
+            fixed_delta: 1.0 / 120.0,
+            substeps: 1,
+            collision_response: CollisionResponse::Bounce,
+            boundary: Boundary::Reflect,
+            restitution: 0.85,
+            event_horizon: true,
+            trails: true,
+            trail_length: 240,
+        }
+    }
+}
+
+impl Settings {
+    fn of(world: &World) -> Self {
+        Settings {
+            g: world.gravity.g,
+            softening: world.gravity.softening,
+            gravity_solver: world.gravity_solver,
+            theta: world.theta,
+            integrator: world.integrator,
+            fixed_delta: world.fixed_delta,
+            substeps: world.substeps,
+            collision_response: world.collision_response,
+            boundary: world.boundary,
+            restitution: world.restitution,
+            event_horizon: world.event_horizon,
+            trails: world.trails,
+            trail_length: world.trail_length,
+        }
+    }
+
+    fn apply(&self, world: &mut World) {
+        world.gravity.g = self.g;
+        world.gravity.softening = self.softening;
+        world.gravity_solver = self.gravity_solver;
+        world.theta = self.theta;
+        world.integrator = self.integrator;
+        world.fixed_delta = self.fixed_delta;
+        world.substeps = self.substeps.max(1);
+        world.collision_response = self.collision_response;
+        world.boundary = self.boundary;
+        world.restitution = self.restitution;
+        world.event_horizon = self.event_horizon;
+        world.trails = self.trails;
+        world.trail_length = self.trail_length.min(MAX_TRAIL_LENGTH);
+    }
+}
+
+impl Scene {
+    pub fn capture(world: &World) -> Self {
+        Scene {
+            settings: Settings::of(world),
+            balls: world
+                .balls
+                .iter()
+                .map(|b| BallDescription {
+                    position: b.position,
+                    velocity: b.velocity,
+                    radius: b.radius,
+                    color: b.color,
+                    mass: b.mass,
+                    has_collision: b.has_collision,
+                    has_gravity: b.has_gravity,
+                    is_black_hole: b.is_black_hole,
+                    potential: b.potential,
+                })
+                .collect(),
+            squares: world.squares.clone(),
+            polygons: world
+                .polygons
+                .iter()
+                .map(|p| PolygonDescription {
+                    position: p.position,
+                    velocity: p.velocity,
+                    rotation: p.rotation,
+                    angular_velocity: p.angular_velocity,
+                    vertices: p.vertices.clone(),
+                    color: p.color,
+                    mass: p.mass,
+                    has_gravity: p.has_gravity,
+                })
+                .collect(),
+            segments: world.segments.clone(),
+        }
+    }
+
+    /// Replaces everything in `world` with this scene. The world keeps its size.
+    pub fn apply(&self, world: &mut World) {
+        world.clear();
+        self.settings.apply(world);
+
+        for description in &self.balls {
+            let mut ball = BallObject::new(
+                description.position,
+                description.velocity,
+                description.radius,
+                description.color,
+                description.mass,
+                description.has_collision,
+                description.has_gravity,
+            );
+            ball.is_black_hole = description.is_black_hole;
+            ball.potential = description.potential;
+            world.add_ball(ball);
+        }
+        for square in &self.squares {
+            world.add_square(square.clone());
+        }
+        for description in &self.polygons {
+            let Some(mut polygon) = PolygonObject::new(
+                description.position,
+                description.velocity,
+                description.vertices.clone(),
+                description.color,
+                description.mass,
+                description.has_gravity,
+            ) else {
+                continue;
+            };
+            polygon.rotation = description.rotation;
+            polygon.previous_rotation = description.rotation;
+            polygon.angular_velocity = description.angular_velocity;
+            world.add_polygon(polygon);
+        }
+        for segment in &self.segments {
+            world.add_segment(segment.clone());
+        }
+        // The horizon follows from `g`, whatever radius the file gives
+        world.resize_black_holes();
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let scene: Scene = toml::from_str(&fs::read_to_string(path)?)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    /// Rejects values the simulation can't run with.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let settings = &self.settings;
+        let numbers = [
+            settings.g,
+            settings.softening,
+            settings.theta,
+            settings.fixed_delta,
+            settings.restitution,
+        ];
+        if !numbers.iter().all(|n| n.is_finite()) {
+            return Err("settings must be finite numbers".into());
+        }
+        if settings.fixed_delta < MIN_FIXED_DELTA {
+            return Err(format!(
+                "fixed_delta must be at least {MIN_FIXED_DELTA}, got {}",
+                settings.fixed_delta
+            )
+            .into());
+        }
+        if settings.softening < 0.0 {
+            return Err(
+                format!("softening must not be negative, got {}", settings.softening).into(),
+            );
+        }
+        if settings.theta < 0.0 {
+            return Err(format!("theta must not be negative, got {}", settings.theta).into());
+        }
+        if !(0.0..=1.0).contains(&settings.restitution) {
+            return Err(format!(
+                "restitution must be between 0 and 1, got {}",
+                settings.restitution
+            )
+            .into());
+        }
+
+        for (i, ball) in self.balls.iter().enumerate() {
+            let finite = ball.position.is_finite()
+                && ball.velocity.is_finite()
+                && ball.radius.is_finite()
+                && ball.mass.is_finite();
+            if !finite {
+                return Err(format!("ball {i} has a number that isn't finite").into());
+            }
+            if ball.radius < 0.0 || ball.mass < 0.0 {
+                return Err(format!("ball {i} has a negative radius or mass").into());
+            }
+        }
+        for (i, polygon) in self.polygons.iter().enumerate() {
+            let finite = polygon.position.is_finite()
+                && polygon.velocity.is_finite()
+                && polygon.rotation.is_finite()
+                && polygon.angular_velocity.is_finite()
+                && polygon.mass.is_finite()
+                && polygon.vertices.iter().all(|v| v.is_finite());
+            if !finite {
+                return Err(format!("polygon {i} has a number that isn't finite").into());
+            }
+            if polygon.mass < 0.0 {
+                return Err(format!("polygon {i} has a negative mass").into());
+            }
+            let polygon_object = PolygonObject::new(
+                polygon.position,
+                polygon.velocity,
+                polygon.vertices.clone(),
+                polygon.color,
+                polygon.mass,
+                polygon.has_gravity,
+            );
+            if polygon_object.is_none() {
+                return Err(
+                    format!("polygon {i} needs at least 3 vertices that aren't collinear").into(),
+                );
+            }
+        }
+        for (i, square) in self.squares.iter().enumerate() {
+            let finite = square.position.is_finite()
+                && square.size.is_finite()
+                && square.rotation.is_finite();
+            if !finite {
+                return Err(format!("square {i} has a number that isn't finite").into());
+            }
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if !(segment.start.is_finite() && segment.end.is_finite()) {
+                return Err(format!("segment {i} has a number that isn't finite").into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_match_a_new_world() {
+        let defaults = toml::to_string(&Settings::default()).unwrap();
+        let world = toml::to_string(&Settings::of(&World::new(0.0, 0.0))).unwrap();
+        assert_eq!(defaults, world);
+    }
+
+    #[test]
+    fn validate_rejects_what_the_simulation_cannot_run() {
+        let rejected = [
+            "[settings]\nfixed_delta = nan",
+            "[settings]\nfixed_delta = 1e-9",
+            "[settings]\ntheta = nan",
+            "[settings]\nsoftening = -1.0",
+            "[settings]\nrestitution = 2.0",
+            "[[balls]]\nposition = [0.0, 0.0, 0.0]\nradius = nan\nmass = inf\ncolor = { r = 0, g = 0, b = 0, a = 255 }",
+            "[[polygons]]\nposition = [0.0, 0.0, 0.0]\nvertices = [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]]\nmass = 1.0\ncolor = { r = 0, g = 0, b = 0, a = 255 }",
+        ];
+        for source in rejected {
+            let scene: Scene = toml::from_str(source).unwrap();
+            assert!(scene.validate().is_err(), "accepted {source:?}");
+        }
+
+        assert!(toml::from_str::<Scene>("[settings]\nunknown = 1").is_err());
+        assert!(toml::from_str::<Scene>("").unwrap().validate().is_ok());
+    }
+}
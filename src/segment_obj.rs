@@ -0,0 +1,29 @@
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::ball_obj::Color;
+
+/// Static wall drawn as a line from `start` to `end`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SegmentObject {
+    pub start: Vec3,
+    pub end: Vec3,
+    pub color: Color,
+}
+
+impl SegmentObject {
+    pub fn new(start: Vec3, end: Vec3, color: Color) -> Self {
+        SegmentObject { start, end, color }
+    }
+
+    pub fn get_vertices(&self) -> Vec<Vec2> {
+        vec![self.start.truncate(), self.end.truncate()]
+    }
+
+    // A segment is a flat polygon, so one axis separates it from anything beside it
+    pub fn get_normals(&self) -> Vec<Vec2> {
+        let edge = (self.end - self.start).truncate();
+        vec![Vec2::new(-edge.y, edge.x).normalize_or_zero()]
+    }
+}
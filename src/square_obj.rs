@@ -1,62 +1,25 @@
-extern crate glfw;
-use glam::{Mat4, Vec2, Vec3};
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
 
+use crate::ball_obj::Color;
+
+/// Static square obstacle.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SquareObject {
     pub position: Vec3,
     pub size: f32,
     pub rotation: f32,
-    pub color: Vec3,
-    vao: u32,
-    vbo: u32,
-    ebo: u32,
+    pub color: Color,
 }
 
 impl SquareObject {
-    pub fn new(position: Vec3, rotation: f32, size: f32, color: Vec3) -> Self {
-        let mut square = SquareObject {
+    pub fn new(position: Vec3, rotation: f32, size: f32, color: Color) -> Self {
+        SquareObject {
             position,
             size,
             rotation,
             color,
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
-        };
-
-        square.mesh();
-
-        square
-    }
-
-    pub fn render(&self, shader_program: u32, projection: &Mat4) {
-        unsafe {
-            gl::UseProgram(shader_program);
-
-            let mut model = glam::Mat4::IDENTITY;
-
-            model *= Mat4::from_translation(self.position);
-
-            if self.rotation != 0.0 {
-                model *= Mat4::from_axis_angle(Vec3::Z, self.rotation);
-            }
-
-            let transform = *projection * model;
-
-            let transform_name = std::ffi::CString::new("transform").unwrap();
-            let transformloc = gl::GetUniformLocation(shader_program, transform_name.as_ptr());
-            gl::UniformMatrix4fv(
-                transformloc,
-                1,
-                gl::FALSE,
-                &transform as *const Mat4 as *const f32,
-            );
-
-            let color_name = std::ffi::CString::new("objectColor").unwrap();
-            let colorloc = gl::GetUniformLocation(shader_program, color_name.as_ptr());
-            gl::Uniform3f(colorloc, self.color.x, self.color.y, self.color.z);
-
-            gl::BindVertexArray(self.vao);
-            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null());
         }
     }
 
@@ -88,6 +51,23 @@ impl SquareObject {
         )
     }
 
+    pub fn get_vertices(&self) -> Vec<Vec2> {
+        let half = self.size / 2.0;
+
+        let cos = self.rotation.cos();
+        let sin = self.rotation.sin();
+
+        [
+            Vec2::new(half, half),   // oben rechts
+            Vec2::new(half, -half),  // unten rechts
+            Vec2::new(-half, -half), // unten links
+            Vec2::new(-half, half),  // oben links
+        ]
+        .iter()
+        .map(|v| Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos) + self.position.truncate())
+        .collect()
+    }
+
     pub fn get_normals(&self) -> Vec<Vec2> {
         let half = self.size / 2.0;
 
@@ -122,55 +102,4 @@ impl SquareObject {
 
         normals
     }
-
-    fn mesh(&mut self) {
-        let half = self.size / 2.0;
-        let vertices: Vec<f32> = vec![
-            half, half, 0.0, // oben rechts
-            half, -half, 0.0, // unten rechts
-            -half, -half, 0.0, // unten links
-            -half, half, 0.0, // oben links
-        ];
-
-        let indices: Vec<u32> = vec![0, 1, 3, 1, 2, 3];
-        unsafe {
-            gl::GenVertexArrays(1, &mut self.vao);
-            gl::GenBuffers(1, &mut self.vbo);
-            gl::GenBuffers(1, &mut self.ebo);
-
-            gl::BindVertexArray(self.vao);
-
-            //VBO
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (vertices.len() * std::mem::size_of::<f32>()) as isize,
-                vertices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            //EBO
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (indices.len() * std::mem::size_of::<u32>()) as isize,
-                indices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            //position
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                (3 * std::mem::size_of::<f32>()) as i32,
-                std::ptr::null(),
-            );
-            gl::EnableVertexAttribArray(0);
-
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-            gl::BindVertexArray(0);
-        }
-    }
 }
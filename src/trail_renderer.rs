@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use glam::{Mat4, Vec3, Vec4};
+
+use blackhole::world::World;
+
+// x, y, z, r, g, b, a
+const FLOATS_PER_VERTEX: usize = 7;
+
+/// Draws every ball and polygon trail of a `World` as fading lines in one draw call.
+pub struct TrailRenderer {
+    shader_program: u32,
+    vao: u32,
+    vbo: u32,
+}
+
+impl TrailRenderer {
+    pub fn new(shader_program: u32) -> Self {
+        let mut vao = 0;
+        let mut vbo = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            let stride = (FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as i32;
+
+            //position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            //color
+            gl::VertexAttribPointer(
+                1,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+
+        TrailRenderer {
+            shader_program,
+            vao,
+            vbo,
+        }
+    }
+
+    pub fn render(&self, world: &World, projection: &Mat4) {
+        let mut vertices: Vec<f32> = Vec::new();
+
+        for ball in &world.balls {
+            push_trail(&mut vertices, &ball.trail, ball.color.to_vec());
+        }
+        for polygon in &world.polygons {
+            push_trail(&mut vertices, &polygon.trail, polygon.color.to_vec());
+        }
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        unsafe {
+            gl::UseProgram(self.shader_program);
+
+            let transform_name = std::ffi::CString::new("transform").unwrap();
+            let transform_loc =
+                gl::GetUniformLocation(self.shader_program, transform_name.as_ptr());
+            gl::UniformMatrix4fv(
+                transform_loc,
+                1,
+                gl::FALSE,
+                projection as *const Mat4 as *const f32,
+            );
+
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::STREAM_DRAW,
+            );
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::LINES, 0, (vertices.len() / FLOATS_PER_VERTEX) as i32);
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+}
+
+fn push_trail(vertices: &mut Vec<f32>, trail: &VecDeque<Vec3>, color: Vec4) {
+    let len = trail.len();
+
+    for (i, (start, end)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+        // Oldest segment is transparent, newest has the body's own alpha
+        let start_alpha = color.w * i as f32 / len as f32;
+        let end_alpha = color.w * (i + 1) as f32 / len as f32;
+        vertices.extend_from_slice(&[
+            start.x,
+            start.y,
+            start.z,
+            color.x,
+            color.y,
+            color.z,
+            start_alpha,
+        ]);
+        vertices.extend_from_slice(&[end.x, end.y, end.z, color.x, color.y, color.z, end_alpha]);
+    }
+}
@@ -0,0 +1,892 @@
+use glam::{Vec2, Vec3};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    ball_obj::{BallObject, Color},
+    broadphase::SpatialHash,
+    collision::{
+        Boundary, CollisionResponse, Contact, ContactBody, check_polygon_circle_collision,
+        check_polygon_polygon_collision, is_outside, resolve_contact,
+    },
+    diagnostics::Diagnostics,
+    gravity::{Gravity, GravitySolver},
+    integrator::Integrator,
+    polygon_obj::PolygonObject,
+    quadtree::QuadTree,
+    segment_obj::SegmentObject,
+    square_obj::SquareObject,
+};
+
+/// Upper bound of `World::trail_length`
+pub const MAX_TRAIL_LENGTH: usize = 4096;
+
+/// Smallest `World::fixed_delta` a scene may ask for
+pub const MIN_FIXED_DELTA: f32 = 1e-4;
+
+// Fixed steps one `advance` may take before it gives up catching up
+const MAX_STEPS_PER_ADVANCE: u32 = 1000;
+
+/// Simulation state without any GL or window dependency.
+#[derive(Clone)]
+pub struct World {
+    pub balls: Vec<BallObject>,
+    pub squares: Vec<SquareObject>,
+    pub polygons: Vec<PolygonObject>,
+    pub segments: Vec<SegmentObject>,
+    pub width: f32,
+    pub height: f32,
+    pub integrator: Integrator,
+    pub gravity: Gravity,
+    pub gravity_solver: GravitySolver,
+    /// Barnes-Hut opening angle. 0 is exact, larger is faster and coarser.
+    pub theta: f32,
+    pub collision_response: CollisionResponse,
+    pub boundary: Boundary,
+    /// Fraction of the speed kept when bouncing off a `Boundary::Reflect` edge and in
+    /// polygon contacts
+    pub restitution: f32,
+    /// Black holes swallow bodies that cross their horizon instead of colliding with them.
+    pub event_horizon: bool,
+    pub swallowed_mass: f32,
+    /// `(gone, survivor)` ids of the balls and polygons merged or swallowed during the last
+    /// `advance`
+    pub absorbed: Vec<(u64, u64)>,
+    pub trails: bool,
+    /// Positions kept per trail, one per fixed step
+    pub trail_length: usize,
+    pub fixed_delta: f32,
+    pub substeps: u32,
+    pub tick: u64,
+    /// Source of spawn colours, seeded so replays get the same ones. ChaCha rather than
+    /// `StdRng`, whose algorithm may change between rand versions.
+    pub rng: ChaCha8Rng,
+    next_id: u64,
+    accumulator: f32,
+    diagnostics_baseline: Option<Diagnostics>,
+}
+
+impl World {
+    pub fn new(width: f32, height: f32) -> Self {
+        World {
+            balls: Vec::new(),
+            squares: Vec::new(),
+            polygons: Vec::new(),
+            segments: Vec::new(),
+            width,
+            height,
+            integrator: Integrator::VelocityVerlet,
+            gravity: Gravity::default(),
+            gravity_solver: GravitySolver::DirectSum,
+            theta: 0.5,
+            collision_response: CollisionResponse::Bounce,
+            boundary: Boundary::Reflect,
+            restitution: 0.85,
+            event_horizon: true,
+            swallowed_mass: 0.0,
+            absorbed: Vec::new(),
+            trails: true,
+            trail_length: 240,
+            fixed_delta: 1.0 / 120.0,
+            substeps: 1,
+            tick: 0,
+            rng: ChaCha8Rng::from_os_rng(),
+            next_id: 0,
+            accumulator: 0.0,
+            diagnostics_baseline: None,
+        }
+    }
+
+    pub fn add_ball(&mut self, mut ball: BallObject) {
+        ball.id = self.next_id;
+        self.next_id += 1;
+        self.balls.push(ball);
+        self.reset_diagnostics();
+    }
+
+    pub fn add_square(&mut self, square: SquareObject) {
+        self.squares.push(square);
+    }
+
+    pub fn add_polygon(&mut self, mut polygon: PolygonObject) {
+        polygon.id = self.next_id;
+        self.next_id += 1;
+        self.polygons.push(polygon);
+        self.reset_diagnostics();
+    }
+
+    pub fn add_segment(&mut self, segment: SegmentObject) {
+        self.segments.push(segment);
+    }
+
+    pub fn clear(&mut self) {
+        self.balls.clear();
+        self.squares.clear();
+        self.polygons.clear();
+        self.segments.clear();
+        // Ids start over so a replay hands out the same ones as the recorded session
+        self.next_id = 0;
+        self.swallowed_mass = 0.0;
+        self.absorbed.clear();
+        self.reset_diagnostics();
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics::measure(&self.balls, &self.polygons, &self.gravity)
+    }
+
+    /// State the current drift is measured against. Taken lazily after the last reset.
+    pub fn diagnostics_baseline(&mut self) -> Diagnostics {
+        match self.diagnostics_baseline {
+            Some(baseline) => baseline,
+            None => {
+                let baseline = self.diagnostics();
+                self.diagnostics_baseline = Some(baseline);
+                baseline
+            }
+        }
+    }
+
+    /// Sizes black holes to their Schwarzschild radius. Call after changing `gravity.g`.
+    pub fn resize_black_holes(&mut self) {
+        for ball in self.balls.iter_mut().filter(|b| b.is_black_hole) {
+            ball.radius = self.gravity.schwarzschild_radius(ball.mass);
+        }
+    }
+
+    /// Call after changing bodies or parameters outside of the simulation.
+    pub fn reset_diagnostics(&mut self) {
+        self.diagnostics_baseline = None;
+    }
+
+    /// Resets the clock and reseeds the colours, as at the start of a recording.
+    pub fn restart(&mut self, seed: u64) {
+        self.tick = 0;
+        self.accumulator = 0.0;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    pub fn random_color(&mut self) -> Color {
+        // Raw bytes, so no sampling algorithm of rand sits between the seed and the colour
+        let [r, g, b, _] = self.rng.next_u32().to_le_bytes();
+        Color::new(r, g, b, 255)
+    }
+
+    /// Consumes `frame_time` in fixed steps of `fixed_delta`.
+    /// Returns how far the leftover time is between the previous and the current state.
+    pub fn advance(&mut self, frame_time: f32) -> f32 {
+        self.advance_with(frame_time, |_| {})
+    }
+
+    /// Like `advance`, calling `before_step` ahead of every fixed step.
+    pub fn advance_with(
+        &mut self,
+        frame_time: f32,
+        mut before_step: impl FnMut(&mut World),
+    ) -> f32 {
+        self.absorbed.clear();
+        self.accumulator += frame_time;
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_delta {
+            if steps == MAX_STEPS_PER_ADVANCE {
+                // Drop the backlog rather than stall the frame
+                self.accumulator %= self.fixed_delta;
+                break;
+            }
+            before_step(self);
+            self.fixed_step();
+            self.accumulator -= self.fixed_delta;
+            steps += 1;
+        }
+        self.accumulator / self.fixed_delta
+    }
+
+    pub fn fixed_step(&mut self) {
+        for ball in &mut self.balls {
+            ball.previous_position = ball.position;
+        }
+        for polygon in &mut self.polygons {
+            polygon.previous_position = polygon.position;
+            polygon.previous_rotation = polygon.rotation;
+        }
+
+        let substeps = self.substeps.max(1);
+        let delta_time = self.fixed_delta / substeps as f32;
+        for _ in 0..substeps {
+            self.step(delta_time);
+        }
+        self.record_trails();
+        self.tick += 1;
+    }
+
+    fn record_trails(&mut self) {
+        let bodies = self
+            .balls
+            .iter_mut()
+            .map(|b| (&mut b.trail, b.position))
+            .chain(self.polygons.iter_mut().map(|p| (&mut p.trail, p.position)));
+        for (trail, position) in bodies {
+            if !self.trails {
+                trail.clear();
+                continue;
+            }
+            trail.push_back(position);
+            while trail.len() > self.trail_length {
+                trail.pop_front();
+            }
+        }
+    }
+
+    pub fn step(&mut self, delta_time: f32) {
+        // Balls and polygons pull on each other, so they are integrated together
+        let mut positions: Vec<Vec3> = self
+            .balls
+            .iter()
+            .map(|b| b.position)
+            .chain(self.polygons.iter().map(|p| p.position))
+            .collect();
+        let mut velocities: Vec<Vec3> = self
+            .balls
+            .iter()
+            .map(|b| b.velocity)
+            .chain(self.polygons.iter().map(|p| p.velocity))
+            .collect();
+
+        self.integrator
+            .step(&mut positions, &mut velocities, delta_time, |p| {
+                self.accelerations(p)
+            });
+
+        let polygon_positions = positions.split_off(self.balls.len());
+        let polygon_velocities = velocities.split_off(self.balls.len());
+        for (ball, (position, velocity)) in self
+            .balls
+            .iter_mut()
+            .zip(positions.into_iter().zip(velocities))
+        {
+            ball.position = position;
+            ball.velocity = velocity;
+        }
+        for (polygon, (position, velocity)) in self
+            .polygons
+            .iter_mut()
+            .zip(polygon_positions.into_iter().zip(polygon_velocities))
+        {
+            polygon.position = position;
+            polygon.velocity = velocity;
+            polygon.rotation += polygon.angular_velocity * delta_time;
+        }
+
+        if self.event_horizon {
+            self.absorb_into_black_holes();
+        }
+        self.resolve_collisions();
+    }
+
+    fn absorb_into_black_holes(&mut self) {
+        let len = self.balls.len();
+        let mut swallowed = vec![false; len];
+
+        for h in 0..len {
+            if !self.balls[h].is_black_hole || swallowed[h] {
+                continue;
+            }
+            let captured: Vec<usize> = (0..len)
+                .filter(|&b| {
+                    let body = &self.balls[b];
+                    let hole = &self.balls[h];
+                    // A black hole can only be swallowed by a heavier one
+                    b != h
+                        && !swallowed[b]
+                        && !(body.is_black_hole && body.mass > hole.mass)
+                        && (body.position - hole.position).length() < hole.radius
+                })
+                .collect();
+
+            for b in captured {
+                let body = self.balls[b].clone();
+                let hole = &mut self.balls[h];
+                swallow(hole, body.mass, body.velocity, &self.gravity);
+
+                self.swallowed_mass += body.mass;
+                self.absorbed.push((body.id, hole.id));
+                swallowed[b] = true;
+            }
+        }
+
+        self.remove_flagged(&swallowed);
+
+        // Polygons go once their centroid crosses the horizon, like balls
+        let mut captured = vec![false; self.polygons.len()];
+        for hole in self.balls.iter_mut().filter(|b| b.is_black_hole) {
+            for (polygon, captured) in self.polygons.iter().zip(&mut captured) {
+                if *captured || (polygon.position - hole.position).length() >= hole.radius {
+                    continue;
+                }
+                swallow(hole, polygon.mass, polygon.velocity, &self.gravity);
+
+                self.swallowed_mass += polygon.mass;
+                self.absorbed.push((polygon.id, hole.id));
+                *captured = true;
+            }
+        }
+        if captured.contains(&true) {
+            let mut index = 0;
+            self.polygons.retain(|_| {
+                index += 1;
+                !captured[index - 1]
+            });
+            self.reset_diagnostics();
+        }
+    }
+
+    fn remove_flagged(&mut self, removed: &[bool]) {
+        let mut index = 0;
+        self.balls.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+        if removed.contains(&true) {
+            self.reset_diagnostics();
+        }
+    }
+
+    /// Id of the ball that the body `id` ended up in during the last `advance`, `id` itself
+    /// if it wasn't merged or swallowed.
+    pub fn survivor(&self, mut id: u64) -> u64 {
+        while let Some(&(_, survivor)) = self.absorbed.iter().find(|&&(gone, _)| gone == id) {
+            id = survivor;
+        }
+        id
+    }
+
+    /// Topmost ball covering `position`.
+    pub fn ball_at(&self, position: Vec3) -> Option<usize> {
+        self.balls
+            .iter()
+            .rposition(|b| (b.position - position).length() < b.radius)
+    }
+
+    /// Mass-weighted centre of all balls and polygons, `alpha` blending as in rendering.
+    pub fn barycentre(&self, alpha: f32) -> Option<Vec3> {
+        let bodies: Vec<(Vec3, f32)> = self
+            .balls
+            .iter()
+            .map(|b| (b.previous_position.lerp(b.position, alpha), b.mass))
+            .chain(
+                self.polygons
+                    .iter()
+                    .map(|p| (p.previous_position.lerp(p.position, alpha), p.mass)),
+            )
+            .collect();
+        let total_mass: f32 = bodies.iter().map(|&(_, mass)| mass).sum();
+        if total_mass <= 0.0 {
+            return None;
+        }
+        let weighted: Vec3 = bodies.iter().map(|&(position, mass)| position * mass).sum();
+        Some(weighted / total_mass)
+    }
+
+    /// Gravitational acceleration a massless body would feel at `position`.
+    pub fn acceleration_at(&self, position: Vec3) -> Vec3 {
+        let balls: Vec3 = self
+            .balls
+            .iter()
+            .map(|b| {
+                self.gravity
+                    .acceleration(b.position - position, b.mass, b.horizon(&self.gravity))
+            })
+            .sum();
+        let polygons: Vec3 = self
+            .polygons
+            .iter()
+            .map(|p| {
+                self.gravity
+                    .acceleration(p.position - position, p.mass, 0.0)
+            })
+            .sum();
+        balls + polygons
+    }
+
+    /// Body whose pull is strongest at `position`.
+    pub fn dominant_attractor(&self, position: Vec3) -> Option<usize> {
+        self.balls
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.mass > 0.0 && b.position != position)
+            .map(|(i, b)| {
+                let pull = self.gravity.acceleration(
+                    b.position - position,
+                    b.mass,
+                    b.horizon(&self.gravity),
+                );
+                (i, pull.length())
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// Velocity that puts a body at `position` on a counter-clockwise orbit around the
+    /// dominant attractor, with `position` as periapsis. An `eccentricity` of 0 is circular.
+    pub fn orbital_velocity(&self, position: Vec3, eccentricity: f32) -> Option<Vec3> {
+        let attractor = &self.balls[self.dominant_attractor(position)?];
+        let offset = position - attractor.position;
+        let r = offset.length();
+
+        //v_circular^2 = r * a, v_periapsis = v_circular * sqrt(1 + e)
+        let pull =
+            self.gravity
+                .acceleration(-offset, attractor.mass, attractor.horizon(&self.gravity));
+        let speed = (r * pull.length() * (1.0 + eccentricity)).sqrt();
+        let tangent = Vec3::new(-offset.y, offset.x, 0.0) / r;
+
+        Some(attractor.velocity + tangent * speed)
+    }
+
+    /// Path `ghost` would take over `duration` seconds. Other bodies are held where they
+    /// are and nothing in the world is changed.
+    pub fn predict_trajectory(&self, ghost: &BallObject, duration: f32) -> Vec<Vec3> {
+        let steps = (duration / self.fixed_delta).ceil() as usize;
+        let substeps = self.substeps.max(1);
+        let delta_time = self.fixed_delta / substeps as f32;
+        let mut ghost = ghost.clone();
+        let mut points = Vec::with_capacity(steps + 1);
+        points.push(ghost.position);
+
+        // Substeps as in `fixed_step`, one point per fixed step
+        for _ in 0..steps {
+            for _ in 0..substeps {
+                if !self.step_ghost(&mut ghost, delta_time) {
+                    points.push(ghost.position);
+                    return points;
+                }
+            }
+            points.push(ghost.position);
+        }
+
+        points
+    }
+
+    // Moves `ghost` like `step` would. False once it is gone from the world.
+    fn step_ghost(&self, ghost: &mut BallObject, delta_time: f32) -> bool {
+        let mut position = [ghost.position];
+        let mut velocity = [ghost.velocity];
+        self.integrator
+            .step(&mut position, &mut velocity, delta_time, |p| {
+                vec![self.acceleration_at(p[0])]
+            });
+        ghost.position = position[0];
+        ghost.velocity = velocity[0];
+
+        let captured = self.event_horizon
+            && self
+                .balls
+                .iter()
+                .any(|b| b.is_black_hole && (ghost.position - b.position).length() < b.radius);
+        if captured {
+            return false;
+        }
+
+        match self.boundary {
+            Boundary::Reflect => ghost.wall_collision(self.width, self.height, self.restitution),
+            Boundary::Wrap => ghost.wrap(self.width, self.height),
+            Boundary::Absorb => {
+                if is_outside(ghost.position, ghost.radius, self.width, self.height) {
+                    return false;
+                }
+            }
+            Boundary::Open => {}
+        }
+        for square in &self.squares {
+            ghost.check_ball_square_collision(square);
+        }
+        for segment in &self.segments {
+            ghost.check_ball_segment_collision(segment);
+        }
+        true
+    }
+
+    /// Gravitational acceleration of every ball and then every polygon if they were at
+    /// `positions`.
+    pub fn accelerations(&self, positions: &[Vec3]) -> Vec<Vec3> {
+        let mut accelerations = vec![Vec3::ZERO; positions.len()];
+
+        let body_masses: Vec<f32> = self
+            .balls
+            .iter()
+            .map(|b| b.mass)
+            .chain(self.polygons.iter().map(|p| p.mass))
+            .collect();
+        let has_gravity: Vec<bool> = self
+            .balls
+            .iter()
+            .map(|b| b.has_gravity)
+            .chain(self.polygons.iter().map(|p| p.has_gravity))
+            .collect();
+        // A pair uses the Paczynski-Wiita potential if either side carries one
+        let horizons: Vec<f32> = self
+            .balls
+            .iter()
+            .map(|b| b.horizon(&self.gravity))
+            .chain(self.polygons.iter().map(|_| 0.0))
+            .collect();
+
+        match self.gravity_solver {
+            GravitySolver::DirectSum => {
+                for i in 0..positions.len() {
+                    if !has_gravity[i] {
+                        continue;
+                    }
+                    for j in 0..positions.len() {
+                        if i != j {
+                            accelerations[i] += self.gravity.acceleration(
+                                positions[j] - positions[i],
+                                body_masses[j],
+                                horizons[i].max(horizons[j]),
+                            );
+                        }
+                    }
+                }
+            }
+            GravitySolver::BarnesHut => {
+                // Pseudo-Newtonian sources stay out of the tree and are summed exactly
+                let masses: Vec<f32> = body_masses
+                    .iter()
+                    .zip(&horizons)
+                    .map(|(&m, &h)| if h > 0.0 { 0.0 } else { m })
+                    .collect();
+                let sources: Vec<usize> = (0..positions.len())
+                    .filter(|&j| horizons[j] > 0.0)
+                    .collect();
+                let tree = QuadTree::build(positions, &masses);
+                for i in 0..positions.len() {
+                    if !has_gravity[i] {
+                        continue;
+                    }
+                    let pull = |j: usize| {
+                        self.gravity.acceleration(
+                            positions[j] - positions[i],
+                            body_masses[j],
+                            horizons[i].max(horizons[j]),
+                        )
+                    };
+                    // Every pair of a pseudo-Newtonian body is pseudo-Newtonian, so it feels
+                    // all others exactly, the same as in the direct sum
+                    accelerations[i] = if horizons[i] > 0.0 {
+                        (0..positions.len()).filter(|&j| j != i).map(pull).sum()
+                    } else {
+                        tree.acceleration(i, positions[i], self.theta, &self.gravity)
+                            + sources.iter().map(|&j| pull(j)).sum::<Vec3>()
+                    };
+                }
+            }
+        }
+
+        accelerations
+    }
+
+    fn apply_boundary(&mut self) {
+        let (width, height) = (self.width, self.height);
+        match self.boundary {
+            Boundary::Reflect => {
+                for ball in &mut self.balls {
+                    ball.wall_collision(width, height, self.restitution);
+                }
+                for polygon in &mut self.polygons {
+                    for contact in wall_contacts(&polygon.get_vertices(), width, height) {
+                        resolve_fixed_contact(polygon, &contact, self.restitution);
+                    }
+                }
+            }
+            Boundary::Wrap => {
+                for ball in &mut self.balls {
+                    ball.wrap(width, height);
+                }
+                for polygon in &mut self.polygons {
+                    polygon.wrap(width, height);
+                }
+            }
+            Boundary::Absorb => {
+                let outside: Vec<bool> = self
+                    .balls
+                    .iter()
+                    .map(|b| is_outside(b.position, b.radius, width, height))
+                    .collect();
+                self.remove_flagged(&outside);
+                let count = self.polygons.len();
+                self.polygons
+                    .retain(|p| !is_outside(p.position, p.bounding_radius(), width, height));
+                if self.polygons.len() != count {
+                    self.reset_diagnostics();
+                }
+            }
+            Boundary::Open => {}
+        }
+    }
+
+    fn resolve_collisions(&mut self) {
+        self.apply_boundary();
+
+        for ball in &mut self.balls {
+            for square in &self.squares {
+                ball.check_ball_square_collision(square);
+            }
+            for segment in &self.segments {
+                ball.check_ball_segment_collision(segment);
+            }
+        }
+
+        self.resolve_ball_collisions();
+        self.resolve_polygon_collisions();
+    }
+
+    fn resolve_ball_collisions(&mut self) {
+        let colliders: Vec<usize> = (0..self.balls.len())
+            .filter(|&i| self.balls[i].has_collision)
+            .collect();
+        if colliders.len() < 2 {
+            return;
+        }
+
+        // Cells sized to the typical body so big bodies span several cells instead of
+        // making every cell huge
+        let average_radius =
+            colliders.iter().map(|&i| self.balls[i].radius).sum::<f32>() / colliders.len() as f32;
+        let mut grid = SpatialHash::new(average_radius * 2.0);
+        for &i in &colliders {
+            grid.insert(i, self.balls[i].position, self.balls[i].radius);
+        }
+
+        let positions: Vec<Vec3> = self.balls.iter().map(|b| b.position).collect();
+        let radii: Vec<f32> = self.balls.iter().map(|b| b.radius).collect();
+
+        let pairs = grid.candidate_pairs(&positions, &radii);
+
+        match self.collision_response {
+            CollisionResponse::Bounce => {
+                for (i, j) in pairs {
+                    let (left, right) = self.balls.split_at_mut(j);
+                    left[i].check_ball_ball_collision(&mut right[0]);
+                }
+            }
+            CollisionResponse::Merge => {
+                let mut merged = vec![false; self.balls.len()];
+                for (i, j) in pairs {
+                    if merged[i] || merged[j] || !self.balls[i].overlaps(&self.balls[j]) {
+                        continue;
+                    }
+                    // The heavier body survives so black holes and big planets keep their identity
+                    let (keep, gone) = if self.balls[i].mass >= self.balls[j].mass {
+                        (i, j)
+                    } else {
+                        (j, i)
+                    };
+                    let other = self.balls[gone].clone();
+                    self.balls[keep].merge(&other, &self.gravity);
+                    self.absorbed.push((other.id, self.balls[keep].id));
+                    merged[gone] = true;
+                }
+                self.remove_flagged(&merged);
+            }
+        }
+    }
+
+    fn resolve_polygon_collisions(&mut self) {
+        let restitution = self.restitution;
+
+        for polygon in &mut self.polygons {
+            for square in &self.squares {
+                let contact = check_polygon_polygon_collision(
+                    &square.get_vertices(),
+                    &square.get_normals(),
+                    &polygon.get_vertices(),
+                    &polygon.get_normals(),
+                );
+                if let Some(contact) = contact {
+                    resolve_fixed_contact(polygon, &contact, restitution);
+                }
+            }
+            for segment in &self.segments {
+                let contact = check_polygon_polygon_collision(
+                    &segment.get_vertices(),
+                    &segment.get_normals(),
+                    &polygon.get_vertices(),
+                    &polygon.get_normals(),
+                );
+                if let Some(contact) = contact {
+                    resolve_fixed_contact(polygon, &contact, restitution);
+                }
+            }
+        }
+
+        for j in 0..self.polygons.len() {
+            for i in 0..j {
+                let (left, right) = self.polygons.split_at_mut(j);
+                let (a, b) = (&mut left[i], &mut right[0]);
+
+                let reach = a.bounding_radius() + b.bounding_radius();
+                if (b.position - a.position).length() >= reach {
+                    continue;
+                }
+                let contact = check_polygon_polygon_collision(
+                    &a.get_vertices(),
+                    &a.get_normals(),
+                    &b.get_vertices(),
+                    &b.get_normals(),
+                );
+                if let Some(contact) = contact {
+                    let mut body_a = a.contact_body();
+                    let mut body_b = b.contact_body();
+                    resolve_contact(&contact, &mut body_a, &mut body_b, restitution);
+                    a.apply_contact_body(&body_a);
+                    b.apply_contact_body(&body_b);
+                }
+            }
+        }
+
+        for polygon in &mut self.polygons {
+            let reach = polygon.bounding_radius();
+            for ball in self.balls.iter_mut().filter(|b| b.has_collision) {
+                if (ball.position - polygon.position).length() >= reach + ball.radius {
+                    continue;
+                }
+                let contact = check_polygon_circle_collision(
+                    &polygon.get_vertices(),
+                    &polygon.get_normals(),
+                    ball.position.truncate(),
+                    ball.radius,
+                );
+                if let Some(contact) = contact {
+                    let mut body_a = polygon.contact_body();
+                    let mut body_b = ball.contact_body();
+                    resolve_contact(&contact, &mut body_a, &mut body_b, restitution);
+                    polygon.apply_contact_body(&body_a);
+                    ball.apply_contact_body(&body_b);
+                }
+            }
+        }
+    }
+}
+
+// Adds a swallowed body's mass and momentum to `hole` and grows its horizon to match
+fn swallow(hole: &mut BallObject, mass: f32, velocity: Vec3, gravity: &Gravity) {
+    let total_mass = hole.mass + mass;
+    hole.velocity = (hole.velocity * hole.mass + velocity * mass) / total_mass;
+    hole.mass = total_mass;
+    hole.radius = gravity.schwarzschild_radius(hole.mass);
+}
+
+// Deepest vertex past each screen edge, as seen from the wall
+fn wall_contacts(vertices: &[Vec2], width: f32, height: f32) -> Vec<Contact> {
+    let walls = [
+        (Vec2::X, 0.0),
+        (-Vec2::X, -width),
+        (Vec2::Y, 0.0),
+        (-Vec2::Y, -height),
+    ];
+
+    walls
+        .iter()
+        .filter_map(|&(normal, offset)| {
+            let (point, distance) = vertices
+                .iter()
+                .map(|&v| (v, v.dot(normal) - offset))
+                .min_by(|a, b| a.1.total_cmp(&b.1))?;
+            (distance < 0.0).then_some(Contact {
+                normal,
+                depth: -distance,
+                point,
+            })
+        })
+        .collect()
+}
+
+fn resolve_fixed_contact(polygon: &mut PolygonObject, contact: &Contact, restitution: f32) {
+    let mut fixed = ContactBody::fixed(contact.point);
+    let mut body = polygon.contact_body();
+    resolve_contact(contact, &mut fixed, &mut body, restitution);
+    polygon.apply_contact_body(&body);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn ball(position: Vec3, velocity: Vec3, mass: f32) -> BallObject {
+        BallObject::new(
+            position,
+            velocity,
+            2.0,
+            Color::new(255, 255, 255, 255),
+            mass,
+            false,
+            true,
+        )
+    }
+
+    #[test]
+    fn advance_steps_without_a_window() {
+        let mut world = World::new(800.0, 600.0);
+        world.add_ball(ball(Vec3::new(300.0, 300.0, 0.0), Vec3::ZERO, 100.0));
+        world.add_ball(ball(Vec3::new(500.0, 300.0, 0.0), Vec3::ZERO, 100.0));
+
+        let alpha = world.advance(0.5 + world.fixed_delta * 0.5);
+
+        assert_eq!(world.tick, 60);
+        assert!((alpha - 0.5).abs() < 1e-3, "alpha {alpha}");
+        // Pulled towards each other
+        assert!(world.balls[0].position.x > 300.0);
+        assert!(world.balls[1].position.x < 500.0);
+    }
+
+    #[test]
+    fn barnes_hut_without_opening_angle_matches_direct_sum() {
+        let mut world = World::new(1000.0, 1000.0);
+        world.restart(7);
+        for _ in 0..64 {
+            let position = Vec3::new(
+                world.rng.random_range(0.0..1000.0),
+                world.rng.random_range(0.0..1000.0),
+                0.0,
+            );
+            let mass = world.rng.random_range(1.0..100.0);
+            world.add_ball(ball(position, Vec3::ZERO, mass));
+        }
+        let positions: Vec<Vec3> = world.balls.iter().map(|b| b.position).collect();
+
+        world.gravity_solver = GravitySolver::DirectSum;
+        let direct = world.accelerations(&positions);
+        world.gravity_solver = GravitySolver::BarnesHut;
+        world.theta = 0.0;
+        let tree = world.accelerations(&positions);
+
+        for (direct, tree) in direct.iter().zip(&tree) {
+            assert!(
+                (*direct - *tree).length() <= 1e-4 * direct.length(),
+                "{direct} vs {tree}"
+            );
+        }
+    }
+
+    #[test]
+    fn verlet_keeps_two_body_energy() {
+        let mut world = World::new(1000.0, 1000.0);
+        world.integrator = Integrator::VelocityVerlet;
+        world.boundary = Boundary::Open;
+        world.add_ball(ball(Vec3::new(500.0, 500.0, 0.0), Vec3::ZERO, 1000.0));
+        let position = Vec3::new(650.0, 500.0, 0.0);
+        let velocity = world.orbital_velocity(position, 0.3).unwrap();
+        world.add_ball(ball(position, velocity, 1.0));
+
+        let start = world.diagnostics();
+        // Several orbits
+        for _ in 0..20000 {
+            world.fixed_step();
+        }
+
+        let drift = world.diagnostics().energy_drift(&start);
+        assert!(drift.abs() < 1e-3, "energy drifted by {drift}");
+    }
+}
@@ -0,0 +1,282 @@
+use glam::{Mat4, Vec3};
+
+use blackhole::{
+    ball_obj::{BallObject, Color},
+    polygon_obj::PolygonObject,
+    segment_obj::SegmentObject,
+    square_obj::SquareObject,
+    world::World,
+};
+
+use crate::line_renderer::LineRenderer;
+
+struct Mesh {
+    vao: u32,
+    vbo: u32,
+    ebo: u32,
+    index_count: i32,
+}
+
+impl Mesh {
+    fn new(vertices: &[f32], indices: &[u32]) -> Self {
+        let mut mesh = Mesh {
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+            index_count: indices.len() as i32,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut mesh.vao);
+            gl::GenBuffers(1, &mut mesh.vbo);
+            gl::GenBuffers(1, &mut mesh.ebo);
+
+            gl::BindVertexArray(mesh.vao);
+
+            //VBO
+            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(vertices) as isize,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::STATIC_DRAW,
+            );
+
+            //EBO
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, mesh.ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                std::mem::size_of_val(indices) as isize,
+                indices.as_ptr() as *const std::ffi::c_void,
+                gl::STATIC_DRAW,
+            );
+
+            //position
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * std::mem::size_of::<f32>()) as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+        mesh
+    }
+
+    // Unit circle, scaled by the ball radius at draw time
+    fn circle() -> Self {
+        let segments = 32;
+        let mut vertices: Vec<f32> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        vertices.extend_from_slice(&[0.0, 0.0, 0.0]);
+
+        for i in 0..=segments {
+            let angle = (i as f32 / segments as f32) * 2.0 * std::f32::consts::PI;
+            let x = angle.cos();
+            let y = angle.sin();
+            vertices.extend_from_slice(&[x, y, 0.0]);
+        }
+
+        for i in 1..=segments {
+            indices.push(0);
+            indices.push(i);
+            indices.push(i + 1);
+        }
+
+        Mesh::new(&vertices, &indices)
+    }
+
+    // Unit square, scaled by the square size at draw time
+    fn square() -> Self {
+        let vertices: Vec<f32> = vec![
+            0.5, 0.5, 0.0, // oben rechts
+            0.5, -0.5, 0.0, // unten rechts
+            -0.5, -0.5, 0.0, // unten links
+            -0.5, 0.5, 0.0, // oben links
+        ];
+        let indices: Vec<u32> = vec![0, 1, 3, 1, 2, 3];
+
+        Mesh::new(&vertices, &indices)
+    }
+}
+
+/// Draws the state of a `World`. Holds all GL handles so the simulation doesn't have to.
+pub struct WorldRenderer {
+    shader_program: u32,
+    line_renderer: LineRenderer,
+    circle: Mesh,
+    square: Mesh,
+    // Polygons differ in shape, so their vertices are streamed every draw
+    polygon_vao: u32,
+    polygon_vbo: u32,
+}
+
+impl WorldRenderer {
+    pub fn new(shader_program: u32) -> Self {
+        let mut polygon_vao = 0;
+        let mut polygon_vbo = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut polygon_vao);
+            gl::GenBuffers(1, &mut polygon_vbo);
+
+            gl::BindVertexArray(polygon_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, polygon_vbo);
+
+            //position
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (2 * std::mem::size_of::<f32>()) as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+
+        WorldRenderer {
+            shader_program,
+            line_renderer: LineRenderer::new(),
+            circle: Mesh::circle(),
+            square: Mesh::square(),
+            polygon_vao,
+            polygon_vbo,
+        }
+    }
+
+    /// `alpha` blends between the previous and the current physics state.
+    pub fn render(&self, world: &World, alpha: f32, projection: &Mat4) {
+        for square in &world.squares {
+            self.render_square(square, projection);
+        }
+        for segment in &world.segments {
+            self.render_segment(segment, projection);
+        }
+        for polygon in &world.polygons {
+            self.render_polygon(polygon, alpha, projection);
+        }
+        for ball in &world.balls {
+            self.render_ball(ball, alpha, projection);
+        }
+    }
+
+    pub fn render_ball(&self, ball: &BallObject, alpha: f32, projection: &Mat4) {
+        let position = ball.previous_position.lerp(ball.position, alpha);
+
+        let mut model = glam::Mat4::IDENTITY;
+        model *= Mat4::from_translation(position);
+        model *= Mat4::from_scale(Vec3::new(ball.radius, ball.radius, 1.0));
+
+        self.draw_mesh(&self.circle, model, ball.color, projection);
+
+        self.line_renderer.draw_vector(
+            position,
+            Vec3::new(ball.velocity.x, ball.velocity.y, 0.0),
+            50.0 + ball.radius,
+            Vec3::new(1.0, 0.0, 0.0),
+            self.shader_program,
+            projection,
+        );
+    }
+
+    pub fn render_square(&self, square: &SquareObject, projection: &Mat4) {
+        let mut model = glam::Mat4::IDENTITY;
+        model *= Mat4::from_translation(square.position);
+        if square.rotation != 0.0 {
+            model *= Mat4::from_axis_angle(Vec3::Z, square.rotation);
+        }
+        model *= Mat4::from_scale(Vec3::new(square.size, square.size, 1.0));
+
+        self.draw_mesh(&self.square, model, square.color, projection);
+    }
+
+    pub fn render_segment(&self, segment: &SegmentObject, projection: &Mat4) {
+        self.line_renderer.draw_line(
+            segment.start,
+            segment.end,
+            segment.color.to_vec().truncate(),
+            self.shader_program,
+            projection,
+        );
+    }
+
+    pub fn render_polygon(&self, polygon: &PolygonObject, alpha: f32, projection: &Mat4) {
+        let position = polygon.previous_position.lerp(polygon.position, alpha);
+        let rotation =
+            polygon.previous_rotation + (polygon.rotation - polygon.previous_rotation) * alpha;
+        let vertices: Vec<f32> = polygon
+            .get_vertices_at(position, rotation)
+            .iter()
+            .flat_map(|v| [v.x, v.y])
+            .collect();
+
+        unsafe {
+            gl::UseProgram(self.shader_program);
+
+            let transform_name = std::ffi::CString::new("transform").unwrap();
+            let transformloc = gl::GetUniformLocation(self.shader_program, transform_name.as_ptr());
+            gl::UniformMatrix4fv(
+                transformloc,
+                1,
+                gl::FALSE,
+                projection as *const Mat4 as *const f32,
+            );
+
+            let color_name = std::ffi::CString::new("objectColor").unwrap();
+            let colorloc = gl::GetUniformLocation(self.shader_program, color_name.as_ptr());
+            let color = polygon.color.to_vec();
+            gl::Uniform4f(colorloc, color.x, color.y, color.z, color.w);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.polygon_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.len() * std::mem::size_of::<f32>()) as isize,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::STREAM_DRAW,
+            );
+
+            gl::BindVertexArray(self.polygon_vao);
+            gl::DrawArrays(gl::TRIANGLE_FAN, 0, (vertices.len() / 2) as i32);
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+
+    fn draw_mesh(&self, mesh: &Mesh, model: Mat4, color: Color, projection: &Mat4) {
+        unsafe {
+            gl::UseProgram(self.shader_program);
+
+            let transform = *projection * model;
+
+            let transform_name = std::ffi::CString::new("transform").unwrap();
+            let transformloc = gl::GetUniformLocation(self.shader_program, transform_name.as_ptr());
+            gl::UniformMatrix4fv(
+                transformloc,
+                1,
+                gl::FALSE,
+                &transform as *const Mat4 as *const f32,
+            );
+
+            let color_name = std::ffi::CString::new("objectColor").unwrap();
+            let colorloc = gl::GetUniformLocation(self.shader_program, color_name.as_ptr());
+            let color = color.to_vec();
+            gl::Uniform4f(colorloc, color.x, color.y, color.z, color.w);
+
+            gl::BindVertexArray(mesh.vao);
+            gl::DrawElements(
+                gl::TRIANGLES,
+                mesh.index_count,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
+        }
+    }
+}